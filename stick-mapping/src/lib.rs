@@ -0,0 +1,308 @@
+//! Conversion from TOML mapping files (see `sdb/README.md`) to the database
+//! format loaded by `stick::Remap`.
+//!
+//! Shared by `stick-remapd`, for loading user mapping files, and `xtask`, for
+//! generating and checking the built-in database.
+
+use std::{collections::HashMap, fmt::Write};
+
+use serde_derive::Deserialize;
+use stick::Event;
+
+#[derive(Deserialize)]
+struct Map {
+    name: String,
+    r#type: String,
+    r#match: Option<Match>,
+    remap: HashMap<String, toml::value::Value>,
+}
+
+#[derive(Deserialize)]
+struct Match {
+    #[serde(default)]
+    ignore: Vec<String>,
+    name: Option<String>,
+    #[serde(default)]
+    priority: i8,
+}
+
+/// Get the code of an event (or button number, from 1 to 127) by name, where
+/// `None` disables the input.
+pub fn event_code(name: &str) -> Option<u8> {
+    match name.parse::<u8>() {
+        Ok(number @ 0x01..=0x7F) => Some(number | 0x80),
+        Ok(_) => None,
+        Err(_) if name == "None" => Some(0x00),
+        Err(_) => (0x01..0x80).find(|&code| {
+            Event::from_code(code, 0.0).is_some_and(|e| e.name() == name)
+        }),
+    }
+}
+
+/// Convert an event name (or button number) to hexadecimal.
+pub fn event_to_hex(name: &str) -> Option<String> {
+    event_code(name).map(|code| format!("{:02X}", code))
+}
+
+/// Convert an input as SDL numbers it (`b3` for a button, `a2` for an axis or
+/// `h0.4` for a hat direction) to hexadecimal.
+pub fn sdl_to_hex(input: &str) -> Option<String> {
+    let number = |n: &str| n.parse::<u8>().ok();
+    if let Some(button) = input.strip_prefix('b') {
+        Some(format!("SB{:02X}", number(button)?))
+    } else if let Some(axis) = input.strip_prefix('a') {
+        Some(format!("SA{:02X}", number(axis)?))
+    } else {
+        let (hat, dir) = input.strip_prefix('h')?.split_once('.')?;
+        let (hat, dir) = (number(hat)?, number(dir)?);
+        if hat > 0xF || ![1, 2, 4, 8].contains(&dir) {
+            return None;
+        }
+        Some(format!("SH{:X}{:X}", hat, dir))
+    }
+}
+
+// Get the `+` (1) or `-` (-1) half of an axis.
+fn half(half: &toml::value::Value) -> Option<i8> {
+    match half.as_str()? {
+        "+" => Some(1),
+        "-" => Some(-1),
+        _ => None,
+    }
+}
+
+// Add the event (and tweaks) an input is mapped to.
+fn push_output(
+    out: &mut String,
+    key: &str,
+    value: toml::value::Value,
+) -> Result<(), String> {
+    let hex = |name: &str| {
+        event_to_hex(name).ok_or_else(|| format!("Unknown event: {}", name))
+    };
+    match value {
+        toml::value::Value::String(event) => {
+            out.push_str(&hex(event.as_str())?);
+        }
+        toml::value::Value::Table(table) => {
+            let bad = |tweak| format!("Bad {} for {}", tweak, key);
+            if let Some(event) = table.get("event") {
+                out.push_str(&hex(event
+                    .as_str()
+                    .ok_or_else(|| bad("event"))?)?);
+            } else {
+                out.push_str(&hex("None")?);
+            }
+            if let Some(max) = table.get("max") {
+                let max = max.as_integer().ok_or_else(|| bad("max"))?;
+                out.push('a');
+                write!(out, "{}", max).unwrap();
+            }
+            if let Some(min) = table.get("min") {
+                let min = min.as_integer().ok_or_else(|| bad("min"))?;
+                out.push('i');
+                write!(out, "{}", min).unwrap();
+            }
+            if let Some(scale) = table.get("scale") {
+                let scale = scale.as_float().ok_or_else(|| bad("scale"))?;
+                out.push('s');
+                write!(out, "{}", scale).unwrap();
+            }
+            if let Some(deadzone) = table.get("deadzone") {
+                let deadzone =
+                    deadzone.as_float().ok_or_else(|| bad("deadzone"))?;
+                out.push('d');
+                write!(out, "{}", deadzone).unwrap();
+            }
+            if let Some(invert) = table.get("invert") {
+                if invert.as_bool().ok_or_else(|| bad("invert"))? {
+                    out.push('v');
+                }
+            }
+            if let Some(half) = table.get("half") {
+                let half = self::half(half).ok_or_else(|| bad("half"))?;
+                write!(out, "h{}", half).unwrap();
+            }
+            if let Some(half) = table.get("output_half") {
+                let half =
+                    self::half(half).ok_or_else(|| bad("output_half"))?;
+                write!(out, "o{}", half).unwrap();
+            }
+        }
+        _map => return Err(format!("Invalid mapping: {:?}", _map)),
+    }
+    Ok(())
+}
+
+/// Convert a TOML mapping file for the controller with hexadecimal `id` to a
+/// database line.
+pub fn to_sdb(id: &str, text: &str) -> Result<String, String> {
+    let file: Map = toml::from_str(text).map_err(|e| e.to_string())?;
+    let hex = |name: &str| {
+        event_to_hex(name).ok_or_else(|| format!("Unknown event: {}", name))
+    };
+    let mut out = String::new();
+
+    // ID of Controller
+    if id.len() != 16 || u64::from_str_radix(id, 16).is_err() {
+        return Err(format!("Bad controller ID: {}", id));
+    }
+    let mut id = id.to_string();
+    for part in file.r#match.iter().flat_map(|m| &m.ignore) {
+        let range = match part.as_str() {
+            "bus" => 0..4,
+            "vendor" => 4..8,
+            "product" => 8..12,
+            "version" => 12..16,
+            _part => return Err(format!("Unknown ID part: {}", _part)),
+        };
+        id.replace_range(range, "****");
+    }
+    out.push_str(&id);
+
+    // Name of Controller.
+    out.push_str(&file.name);
+    out.push('\t');
+
+    // Type of controller
+    let ctlr_type = match file.r#type.as_str() {
+        "xbox" => 'x',
+        "playstation" => 'p',
+        "nintendo" => 'n',
+        "gamepad" => 'g',
+        "flight" => 'f',
+        _type => return Err(format!("Unknown type: {}", _type)),
+    };
+    out.push(ctlr_type);
+
+    // Add remappings
+    let mut maps = Vec::new();
+    let mut kv = Vec::new();
+    for (key, value) in file.remap {
+        kv.push((key, value));
+    }
+    kv.sort_by_key(|a| a.0.to_lowercase());
+    for (key, value) in kv {
+        let input = if let Some(raw) = key.strip_prefix("Raw:") {
+            // Platform-specific kind and code, as hexadecimal.
+            let bad = || format!("Bad raw input: {}", key);
+            let (kind, code) = raw.split_once(':').ok_or_else(bad)?;
+            let kind = u16::from_str_radix(kind, 16).map_err(|_| bad())?;
+            let code = u16::from_str_radix(code, 16).map_err(|_| bad())?;
+            format!("R{:04X}{:04X}", kind, code)
+        } else if let Some(sdl) = key.strip_prefix("Sdl:") {
+            let bad = || format!("Bad SDL input: {}", key);
+            sdl_to_hex(sdl).ok_or_else(bad)?
+        } else {
+            hex(key.as_str())?
+        };
+        // An input can be mapped to a list of events.
+        let values = match value {
+            toml::value::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let mut map = input.clone();
+            push_output(&mut map, &key, value)?;
+            maps.push(map);
+        }
+    }
+    out.push_str(&maps.join(";"));
+
+    // Priority, and pattern for the name.
+    if let Some(Match { name, priority, .. }) = file.r#match {
+        if priority != 0 || name.is_some() {
+            write!(&mut out, "\t{}", priority).unwrap();
+        }
+        if let Some(name) = name {
+            write!(&mut out, "\t{}", name).unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_codes() {
+        assert_eq!(event_code("None"), Some(0x00));
+        assert_eq!(event_code("ActionA"), Some(0x02));
+        assert_eq!(event_code("JoyY"), Some(0x21));
+        assert_eq!(event_code("1"), Some(0x81));
+        assert_eq!(event_code("127"), Some(0xFF));
+        // Numbers that would alias other buttons
+        assert_eq!(event_code("0"), None);
+        assert_eq!(event_code("128"), None);
+        assert_eq!(event_code("Nope"), None);
+        assert_eq!(event_to_hex("2").as_deref(), Some("82"));
+    }
+
+    #[test]
+    fn sdl_inputs() {
+        assert_eq!(sdl_to_hex("b3").as_deref(), Some("SB03"));
+        assert_eq!(sdl_to_hex("a12").as_deref(), Some("SA0C"));
+        assert_eq!(sdl_to_hex("h0.4").as_deref(), Some("SH04"));
+        assert_eq!(sdl_to_hex("h1.8").as_deref(), Some("SH18"));
+        assert_eq!(sdl_to_hex("h0.3"), None);
+        assert_eq!(sdl_to_hex("h16.1"), None);
+        assert_eq!(sdl_to_hex("b"), None);
+        assert_eq!(sdl_to_hex("x1"), None);
+    }
+
+    #[test]
+    fn convert() {
+        let text = r#"
+            name = "Pad"
+            type = "gamepad"
+
+            [match]
+            ignore = ["version"]
+            name = "*Pad*"
+            priority = 2
+
+            [remap]
+            ActionB = "ActionA"
+            JoyZ = [
+                { event = "TriggerL", half = "-" },
+                { event = "TriggerR", half = "+" },
+            ]
+            "Sdl:b1" = { event = "None" }
+            "Raw:0001:0120" = "3"
+        "#;
+        assert_eq!(
+            to_sdb("0300ABCD12340100", text).unwrap(),
+            // Sorted by input, with a map per output
+            "0300ABCD1234****Pad\tg0302;220Eh-1;220Fh1;R0001012083;SB0100\
+             \t2\t*Pad*",
+        );
+    }
+
+    #[test]
+    fn convert_empty() {
+        let text = "name = \"Pad\"\ntype = \"xbox\"\n[remap]\n";
+        assert_eq!(
+            to_sdb("0300ABCD12340100", text).unwrap(),
+            "0300ABCD12340100Pad\tx"
+        );
+    }
+
+    #[test]
+    fn convert_errors() {
+        let text = "name = \"Pad\"\ntype = \"gamepad\"\n[remap]\n";
+        assert!(to_sdb("0300", text).is_err());
+        let bad = |remap: &str| {
+            to_sdb("0300ABCD12340100", &format!("{}{}", text, remap))
+                .unwrap_err()
+        };
+        assert_eq!(bad("Nope = \"ActionA\""), "Unknown event: Nope");
+        assert_eq!(bad("128 = \"ActionA\""), "Unknown event: 128");
+        assert_eq!(bad("\"Sdl:h0.3\" = \"Up\""), "Bad SDL input: Sdl:h0.3");
+        assert_eq!(
+            bad("JoyZ = { event = \"TriggerL\", half = \"x\" }"),
+            "Bad half for JoyZ",
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Remaps controllers with stick, re-emitting each one as a virtual Xbox 360
+//! controller, so games that only understand Xbox controllers can use any
+//! controller in stick's database.
+//!
+//! Physical controllers are grabbed, so games only see the virtual ones, and
+//! rumble from games is forwarded to the physical controller.
+//!
+//! Mapping files use the TOML format from stick's database (see
+//! `sdb/README.md`), named by controller ID (such as
+//! `03004C0568021181.toml`).  They're loaded from the paths passed as
+//! arguments (files or folders), or `~/.config/stick-remapd/` by default, on
+//! top of the built-in database, and reloaded when they change.  Files that
+//! can't be loaded are skipped, and a reload keeps the previous mappings until
+//! every file loads.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{
+        Context,
+        Poll::{self, Pending, Ready},
+        Waker,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use pasts::Loop;
+use stick::{
+    output::{Layout, VirtualController},
+    Controller, Error, Event, Listener, Remap,
+};
+
+/// Name of the virtual controllers (so they aren't remapped again).
+const NAME: &str = "Xbox 360 Controller (stick-remapd)";
+
+/// How often mapping files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+struct State {
+    paths: Vec<PathBuf>,
+    listener: Listener,
+    controllers: Vec<Controller>,
+    pads: Vec<VirtualController>,
+    watch: Watch,
+}
+
+impl State {
+    fn connect(&mut self, mut controller: Controller) -> Poll<Infallible> {
+        for error in self.listener.take_errors() {
+            eprintln!("{}", error);
+        }
+        if controller.name() == NAME {
+            return Pending;
+        }
+        let pad = match VirtualController::new(NAME, Layout::Xbox) {
+            Ok(pad) => pad,
+            Err(error) => {
+                eprintln!("Skipping {}: {}", controller.name(), error);
+                return Pending;
+            }
+        };
+        if !controller.grab(true) {
+            eprintln!("Couldn't grab {}", controller.name());
+        }
+        println!("Connected {:016X}: {}", controller.id(), controller.name());
+        self.controllers.push(controller);
+        self.pads.push(pad);
+        Pending
+    }
+
+    fn event(&mut self, id: usize, event: Event) -> Poll<Infallible> {
+        if let Event::Disconnect = event {
+            let controller = self.controllers.swap_remove(id);
+            self.pads.swap_remove(id);
+            println!(
+                "Disconnected {:016X}: {}",
+                controller.id(),
+                controller.name(),
+            );
+        } else if let Err(error) = self.pads[id].send(event) {
+            eprintln!("{}", error);
+        }
+        Pending
+    }
+
+    fn rumble(
+        &mut self,
+        id: usize,
+        power: Result<(f32, f32), Error>,
+    ) -> Poll<Infallible> {
+        match power {
+            Ok(power) => self.controllers[id].rumble(power),
+            // Release the controller, since it can't be remapped anymore.
+            Err(error) => {
+                let controller = self.controllers.swap_remove(id);
+                self.pads.swap_remove(id);
+                eprintln!("Releasing {}: {}", controller.name(), error);
+            }
+        }
+        Pending
+    }
+
+    fn reload(&mut self, (): ()) -> Poll<Infallible> {
+        println!("Reloading mappings…");
+        // A file that's still being edited shouldn't drop working mappings.
+        let (remap, complete) = load(&self.paths);
+        if !complete {
+            eprintln!("Keeping the previous mappings");
+            return Pending;
+        }
+        // Reconnect every controller with the new mappings.
+        self.controllers.clear();
+        self.pads.clear();
+        self.listener = Listener::new(remap);
+        Pending
+    }
+}
+
+/// Resolves when mapping files change.
+struct Watch(Arc<Mutex<(bool, Option<Waker>)>>);
+
+impl Watch {
+    fn new(paths: Vec<PathBuf>) -> Self {
+        let shared = Arc::new(Mutex::new((false, None::<Waker>)));
+        let watch = Arc::clone(&shared);
+        thread::spawn(move || {
+            let mut last = snapshot(&paths);
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+                let now = snapshot(&paths);
+                if now != last {
+                    last = now;
+                    let mut watch = watch.lock().unwrap();
+                    watch.0 = true;
+                    if let Some(waker) = watch.1.take() {
+                        waker.wake();
+                    }
+                }
+            }
+        });
+        Self(shared)
+    }
+}
+
+impl Future for Watch {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut watch = self.0.lock().unwrap();
+        if std::mem::take(&mut watch.0) {
+            Ready(())
+        } else {
+            watch.1 = Some(cx.waker().clone());
+            Pending
+        }
+    }
+}
+
+// Find the mapping files in `paths`.
+fn files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if let Ok(read_dir) = path.read_dir() {
+            files.extend(
+                read_dir
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension() == Some("toml".as_ref())),
+            );
+        } else if path.exists() {
+            files.push(path.clone());
+        }
+    }
+    files.sort();
+    files
+}
+
+// Get the modification time of each mapping file.
+fn snapshot(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files(paths)
+        .into_iter()
+        .map(|file| {
+            let modified = file.metadata().and_then(|m| m.modified()).ok();
+            (file, modified)
+        })
+        .collect()
+}
+
+// Load the mappings from a mapping file.
+fn load_file(path: &Path) -> Result<Remap, String> {
+    let id = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or("Bad file name")?;
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let line = stick_mapping::to_sdb(&id.to_uppercase(), &text)?;
+    Remap::new()
+        .load(&line)
+        .ok_or_else(|| "Remaps to an unassigned code".to_string())
+}
+
+// Load the built-in database, with the mapping files on top, skipping files
+// that can't be loaded.  Also returns whether every file was loaded.
+fn load(paths: &[PathBuf]) -> (Remap, bool) {
+    let mut remap = Remap::new();
+    let mut complete = true;
+    for file in files(paths) {
+        match load_file(&file) {
+            Ok(mappings) => remap.merge(&mappings),
+            Err(error) => {
+                eprintln!("Skipping {}: {}", file.display(), error);
+                complete = false;
+            }
+        }
+    }
+    (remap, complete)
+}
+
+async fn event_loop(paths: Vec<PathBuf>) {
+    let mut state = State {
+        listener: Listener::new(load(&paths).0),
+        controllers: Vec::new(),
+        pads: Vec::new(),
+        watch: Watch::new(paths.clone()),
+        paths,
+    };
+
+    let exit = Loop::new(&mut state)
+        .when(|s| &mut s.watch, State::reload)
+        .when(|s| &mut s.listener, State::connect)
+        .poll(|s| &mut s.controllers, State::event)
+        .poll(|s| &mut s.pads, State::rumble)
+        .await;
+
+    match exit {}
+}
+
+fn main() {
+    let mut paths: Vec<PathBuf> =
+        std::env::args_os().skip(1).map(PathBuf::from).collect();
+    if paths.iter().any(|path| path.as_os_str() == "--help") {
+        eprintln!("Usage: stick-remapd [PATH]...");
+        eprintln!();
+        eprintln!("Remap controllers as virtual Xbox 360 controllers, with");
+        eprintln!("mapping files (or folders of them) from PATH (default");
+        eprintln!("~/.config/stick-remapd/).");
+        return;
+    }
+    if paths.is_empty() {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config"))
+            });
+        paths.extend(config.map(|config| config.join("stick-remapd")));
+    }
+
+    pasts::block_on(event_loop(paths));
+}
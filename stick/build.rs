@@ -0,0 +1,100 @@
+//! Compiles the mapping databases into static tables, so malformed data fails
+//! the build (rather than `Remap::new()`), and nothing is parsed at runtime.
+
+use std::{fmt::Write, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/sdb.rs"]
+mod sdb;
+
+use sdb::{Input, Map, SdlInput};
+
+const DATABASES: &[(&str, &str)] = &[
+    ("sdlgc_linux.sdb", "sdlgc_linux.rs"),
+    ("remap_linux.sdb", "remap_linux.rs"),
+];
+
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        "f64::NAN".to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn input(input: Input) -> String {
+    match input {
+        Input::Code(code) => format!("Input::Code({})", code),
+        Input::Raw(kind, code) => format!("Input::Raw({}, {})", kind, code),
+        Input::Sdl(SdlInput::Button(i)) => {
+            format!("Input::Sdl(SdlInput::Button({}))", i)
+        }
+        Input::Sdl(SdlInput::Axis(i)) => {
+            format!("Input::Sdl(SdlInput::Axis({}))", i)
+        }
+        Input::Sdl(SdlInput::Hat(i, dir)) => {
+            format!("Input::Sdl(SdlInput::Hat({}, {}))", i, dir)
+        }
+    }
+}
+
+fn map(map: &Map) -> String {
+    format!(
+        "Map {{ deadzone: {}, scale: {}, max: {}, min: {}, invert: {}, \
+            half: {}, out_half: {}, out: {} }}",
+        float(map.deadzone),
+        float(map.scale),
+        map.max,
+        map.min,
+        map.invert,
+        map.half,
+        map.out_half,
+        map.out,
+    )
+}
+
+// Compile a database to a slice of `Builtin`s.
+fn compile(file: &str) -> String {
+    let data = std::fs::read_to_string(file)
+        .unwrap_or_else(|e| panic!("{}: {}", file, e));
+    let mut lines = Vec::new();
+    for (number, line) in data.lines().enumerate() {
+        let line = sdb::parse(line).unwrap_or_else(|| {
+            panic!("{}:{}: malformed mapping", file, number + 1)
+        });
+        lines.push(line);
+    }
+    // Later lines override earlier lines for the same match.  Sorted by mask,
+    // then ID, so controllers are looked up with a binary search per mask.
+    lines.reverse();
+    lines.sort_by_key(|line| (line.mask, line.id, line.priority, line.pattern));
+    lines
+        .dedup_by_key(|line| (line.mask, line.id, line.priority, line.pattern));
+
+    let mut out = String::from("&[\n");
+    for line in lines {
+        write!(
+            out,
+            "Builtin {{ id: {:#018X}, mask: {:#018X}, priority: {}, \
+                pattern: {:?}, type_: {:?}, maps: &[",
+            line.id, line.mask, line.priority, line.pattern, line.type_,
+        )
+        .unwrap();
+        for (i, m) in &line.maps {
+            write!(out, "({}, {}), ", input(*i), map(m)).unwrap();
+        }
+        out.push_str("] },\n");
+    }
+    out.push(']');
+    out
+}
+
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/sdb.rs");
+    for (file, rust) in DATABASES {
+        println!("cargo:rerun-if-changed={}", file);
+        let path = Path::new(&out_dir).join(rust);
+        std::fs::write(path, compile(file)).unwrap();
+    }
+}
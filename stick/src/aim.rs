@@ -0,0 +1,385 @@
+use std::time::Instant;
+
+use crate::{Event, Fusion};
+
+/// Which events [`Aim`] produces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AimOutput {
+    /// [`Event::CamX`] and [`Event::CamY`], as if the right stick was held at
+    /// a deflection proportional to how fast the controller turns.
+    Camera,
+    /// [`Event::MouseX`] and [`Event::MouseY`], as deltas of how far the
+    /// controller turned (in radians, multiplied by sensitivity).
+    Mouse,
+}
+
+/// Which axis turning the controller around is treated as yaw, for [`Aim`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AimSpace {
+    /// Turning around the controller's own vertical axis (predictable, but
+    /// tilting the controller changes which way it aims).
+    Local,
+    /// Turning around the direction of gravity (needs an accelerometer).
+    World,
+    /// Combination of local and world, which works how players expect
+    /// whether the controller is held flat or upright (needs an
+    /// accelerometer).
+    Player,
+}
+
+/// Converts [`Event::Gyro`] into camera or mouse events for aiming.
+///
+/// Axes follow the controller's frame as reported by the platform (`x` to the
+/// right, `y` up when held flat, and `z` towards the player), the same as
+/// [`Fusion`], which finds the direction of gravity for world and player
+/// space.
+///
+/// ```rust,no_run
+/// # async fn run(mut controller: stick::Controller) {
+/// use stick::{Aim, AimOutput, Event};
+///
+/// let mut aim = Aim::new(AimOutput::Mouse).ratchet_button(Event::BumperR);
+/// loop {
+///     let event = (&mut controller).await;
+///     if let Some([x, y]) = aim.process_at(event, controller.time()) {
+///         println!("{} {}", x, y);
+///     }
+///     println!("{}", event);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Aim {
+    output: AimOutput,
+    space: AimSpace,
+    // Multiplier from angular velocity (or angle) to output.
+    sensitivity: f64,
+    // Multiplier at and above `accel_speed`.
+    accel_sensitivity: f64,
+    // Speed (rad/s) where acceleration reaches `accel_sensitivity`.
+    accel_speed: f64,
+    // Speed (rad/s) below which motion is reduced.
+    tightening: f64,
+    // Button id, and whether holding it enables (`true`) or disables aiming.
+    button: Option<(u8, bool)>,
+    // Whether the button is held.
+    held: bool,
+    // Whether the last output was non-zero.
+    moving: bool,
+    // Orientation, for world and player space.
+    fusion: Fusion,
+    // When the last gyroscope reading was.
+    time: Option<Instant>,
+}
+
+impl Aim {
+    /// Create a new aiming stage, in player space.
+    pub fn new(output: AimOutput) -> Self {
+        let sensitivity = match output {
+            AimOutput::Camera => 0.5,
+            AimOutput::Mouse => 1.0,
+        };
+        Self {
+            output,
+            space: AimSpace::Player,
+            sensitivity,
+            accel_sensitivity: sensitivity,
+            accel_speed: 0.0,
+            tightening: 0.0,
+            button: None,
+            held: false,
+            moving: false,
+            fusion: Fusion::new(),
+            time: None,
+        }
+    }
+
+    /// Set which axis is treated as yaw (default [`AimSpace::Player`]).
+    pub fn space(mut self, space: AimSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Set the sensitivity, also removing acceleration (default `0.5` for
+    /// [`AimOutput::Camera`], full deflection at 2 rad/s, and `1.0` for
+    /// [`AimOutput::Mouse`]).
+    pub fn sensitivity(mut self, sensitivity: f64) -> Self {
+        self.sensitivity = sensitivity;
+        self.accel_sensitivity = sensitivity;
+        self
+    }
+
+    /// Increase sensitivity for fast motion, up to `sensitivity` when turning
+    /// at `speed` rad/s or faster.
+    pub fn acceleration(mut self, sensitivity: f64, speed: f64) -> Self {
+        self.accel_sensitivity = sensitivity;
+        self.accel_speed = speed;
+        self
+    }
+
+    /// Reduce motion slower than `speed` rad/s, to hide hand shake (default
+    /// `0.0`, disabled).
+    pub fn tightening(mut self, speed: f64) -> Self {
+        self.tightening = speed;
+        self
+    }
+
+    /// Only aim while a button is held.
+    pub fn enable_button(mut self, button: fn(bool) -> Event) -> Self {
+        self.button = button(false).code().map(|code| (code, true));
+        self
+    }
+
+    /// Stop aiming while a button is held, so the controller can be
+    /// repositioned (ratcheting).
+    pub fn ratchet_button(mut self, button: fn(bool) -> Event) -> Self {
+        self.button = button(false).code().map(|code| (code, false));
+        self
+    }
+
+    /// Update with an event from the controller, received now.  Returns the
+    /// horizontal and vertical events to use for aiming.
+    ///
+    /// Motion events often arrive several at a time, so prefer
+    /// [`process_at()`](Aim::process_at) with
+    /// [`Controller::time()`](crate::Controller::time).
+    pub fn process(&mut self, event: Event) -> Option<[Event; 2]> {
+        self.process_at(event, Instant::now())
+    }
+
+    /// Update with an event from the controller, read at `time`.
+    pub fn process_at(
+        &mut self,
+        event: Event,
+        time: Instant,
+    ) -> Option<[Event; 2]> {
+        self.fusion.process_at(event, time);
+        let (x, y, z) = match event {
+            Event::Gyro { x, y, z } => (x, y, z),
+            event => {
+                let code = event.code();
+                if code.is_some() && self.button.map(|(b, _)| b) == code {
+                    self.held = event.value() != 0.0;
+                }
+                return None;
+            }
+        };
+        let dt = self.time.replace(time).map_or(0.0, |last| {
+            time.saturating_duration_since(last).as_secs_f64()
+        });
+
+        // Turn rate to the right and up (rad/s), after bias calibration.
+        let [bx, by, bz] = self.fusion.bias();
+        let (x, y, z) = (x - bx, y - by, z - bz);
+        let yaw = match self.space {
+            AimSpace::Local => -y,
+            AimSpace::World | AimSpace::Player => {
+                let [gx, gy, gz] = self.fusion.gravity();
+                let norm = (gx * gx + gy * gy + gz * gz).sqrt();
+                let (gx, gy, gz) = if norm > 0.0 {
+                    (gx / norm, gy / norm, gz / norm)
+                } else {
+                    (0.0, 1.0, 0.0)
+                };
+                let world = -(x * gx + y * gy + z * gz);
+                if self.space == AimSpace::World {
+                    world
+                } else {
+                    // Allow some local yaw, for when the controller is held
+                    // upright and yaw becomes roll.
+                    const RELAX: f64 = 1.41;
+                    let local = (y * y + z * z).sqrt();
+                    world.signum() * (world.abs() * RELAX).min(local)
+                }
+            }
+        };
+        let (yaw, pitch) = self.scale(yaw, x);
+
+        let enabled = match self.button {
+            Some((_, enables)) => self.held == enables,
+            None => true,
+        };
+        let (yaw, pitch) = if enabled { (yaw, pitch) } else { (0.0, 0.0) };
+
+        // Only send zero once, when motion stops.
+        let moving = yaw != 0.0 || pitch != 0.0;
+        if !std::mem::replace(&mut self.moving, moving) && !moving {
+            return None;
+        }
+
+        // Vertical axes are positive downwards.
+        Some(match self.output {
+            AimOutput::Camera => [
+                Event::CamX(yaw.clamp(-1.0, 1.0)),
+                Event::CamY((-pitch).clamp(-1.0, 1.0)),
+            ],
+            AimOutput::Mouse => {
+                [Event::MouseX(yaw * dt), Event::MouseY(-pitch * dt)]
+            }
+        })
+    }
+
+    // Apply tightening, acceleration and sensitivity.
+    fn scale(&self, yaw: f64, pitch: f64) -> (f64, f64) {
+        let speed = (yaw * yaw + pitch * pitch).sqrt();
+        let mut scale = if self.accel_speed > 0.0 {
+            let t = (speed / self.accel_speed).min(1.0);
+            self.sensitivity + (self.accel_sensitivity - self.sensitivity) * t
+        } else {
+            self.sensitivity
+        };
+        if speed < self.tightening {
+            scale *= speed / self.tightening;
+        }
+        (yaw * scale, pitch * scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    // Send the events 10 ms apart, returning the last output.
+    fn run(aim: &mut Aim, events: &[Event]) -> Option<[f64; 2]> {
+        let mut time = Instant::now();
+        let mut out = None;
+        for event in events {
+            if let Some([x, y]) = aim.process_at(*event, time) {
+                out = Some([x.value(), y.value()]);
+            }
+            time += Duration::from_millis(10);
+        }
+        out
+    }
+
+    fn gyro(x: f64, y: f64, z: f64) -> Event {
+        Event::Gyro { x, y, z }
+    }
+
+    fn accel([x, y, z]: [f64; 3]) -> Event {
+        Event::Accel { x, y, z }
+    }
+
+    fn close(a: Option<[f64; 2]>, b: [f64; 2]) -> bool {
+        a.is_some_and(|a| {
+            (a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6
+        })
+    }
+
+    const FLAT: [f64; 3] = [0.0, 9.8, 0.0];
+    const UPRIGHT: [f64; 3] = [0.0, 0.0, 9.8];
+
+    #[test]
+    fn local_space() {
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::Local)
+            .sensitivity(1.0);
+        // Turning right (around `y`) and up (around `x`)
+        let out = run(&mut aim, &[gyro(0.25, -0.5, 0.0)]);
+        assert!(close(out, [0.5, -0.25]), "{:?}", out);
+        // Rolling (around `z`) doesn't aim
+        assert!(close(run(&mut aim, &[gyro(0.0, 0.0, 1.0)]), [0.0, 0.0]));
+    }
+
+    #[test]
+    fn world_space() {
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::World)
+            .sensitivity(1.0);
+        let out = run(&mut aim, &[accel(FLAT), gyro(0.0, -0.5, 0.0)]);
+        assert!(close(out, [0.5, 0.0]), "{:?}", out);
+        // Held upright, turning around gravity is around `z`
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::World)
+            .sensitivity(1.0);
+        let out = run(&mut aim, &[accel(UPRIGHT), gyro(0.0, 0.0, -0.5)]);
+        assert!(close(out, [0.5, 0.0]), "{:?}", out);
+    }
+
+    #[test]
+    fn player_space() {
+        // Tilted 45°, world space only sees part of the turn
+        let tilted = [0.0, 9.8 / 2f64.sqrt(), 9.8 / 2f64.sqrt()];
+        let events = [accel(tilted), gyro(0.0, -0.5, 0.0)];
+        let mut world = Aim::new(AimOutput::Camera)
+            .space(AimSpace::World)
+            .sensitivity(1.0);
+        let out = run(&mut world, &events);
+        assert!(close(out, [0.5 / 2f64.sqrt(), 0.0]), "{:?}", out);
+        // Player space relaxes it towards the local turn
+        let mut player = Aim::new(AimOutput::Camera).sensitivity(1.0);
+        let out = run(&mut player, &events);
+        assert!(close(out, [0.5 / 2f64.sqrt() * 1.41, 0.0]), "{:?}", out);
+    }
+
+    #[test]
+    fn mouse_and_sensitivity() {
+        let mut aim = Aim::new(AimOutput::Mouse)
+            .space(AimSpace::Local)
+            .sensitivity(2.0);
+        // Deltas are angle turned, times sensitivity
+        let out = run(&mut aim, &[gyro(0.0, -1.0, 0.0), gyro(0.0, -1.0, 0.0)]);
+        assert!(close(out, [0.02, 0.0]), "{:?}", out);
+        // Camera defaults to full deflection at 2 rad/s
+        let mut aim = Aim::new(AimOutput::Camera).space(AimSpace::Local);
+        let out = run(&mut aim, &[gyro(0.0, -1.0, 0.0)]);
+        assert!(close(out, [0.5, 0.0]), "{:?}", out);
+        assert!(close(run(&mut aim, &[gyro(0.0, -4.0, 0.0)]), [1.0, 0.0]));
+    }
+
+    #[test]
+    fn acceleration_and_tightening() {
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::Local)
+            .sensitivity(0.1)
+            .acceleration(0.3, 2.0);
+        let out = run(&mut aim, &[gyro(0.0, -1.0, 0.0)]);
+        assert!(close(out, [0.2, 0.0]), "{:?}", out);
+        let out = run(&mut aim, &[gyro(0.0, -4.0, 0.0)]);
+        assert!(close(out, [1.0, 0.0]), "{:?}", out);
+
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::Local)
+            .sensitivity(1.0)
+            .tightening(1.0);
+        let out = run(&mut aim, &[gyro(0.0, -0.5, 0.0)]);
+        assert!(close(out, [0.25, 0.0]), "{:?}", out);
+        let out = run(&mut aim, &[gyro(0.0, -2.0, 0.0)]);
+        assert!(close(out, [1.0, 0.0]), "{:?}", out);
+    }
+
+    #[test]
+    fn buttons() {
+        let turn = gyro(0.0, -0.5, 0.0);
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::Local)
+            .sensitivity(1.0)
+            .ratchet_button(Event::BumperR);
+        assert!(close(run(&mut aim, &[turn]), [0.5, 0.0]));
+        // Holding the button stops aiming, sending zero once
+        let out = run(&mut aim, &[Event::BumperR(true), turn]);
+        assert!(close(out, [0.0, 0.0]), "{:?}", out);
+        assert_eq!(run(&mut aim, &[turn]), None);
+        assert!(close(
+            run(&mut aim, &[Event::BumperR(false), turn]),
+            [0.5, 0.0]
+        ));
+
+        let mut aim = Aim::new(AimOutput::Camera)
+            .space(AimSpace::Local)
+            .sensitivity(1.0)
+            .enable_button(Event::BumperL);
+        assert_eq!(run(&mut aim, &[turn]), None);
+        assert!(close(
+            run(&mut aim, &[Event::BumperL(true), turn]),
+            [0.5, 0.0]
+        ));
+        // Other buttons are ignored
+        let out = run(&mut aim, &[Event::BumperR(false), turn]);
+        assert!(close(out, [0.5, 0.0]), "{:?}", out);
+    }
+}
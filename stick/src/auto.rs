@@ -0,0 +1,269 @@
+//! Automatic mappings for controllers that aren't in the mapping database,
+//! guessed from their capabilities and name.
+
+use crate::{
+    sdb::{Input, Map},
+    Capabilities, Event,
+};
+
+// Words in the names of flightsticks.
+const FLIGHT: &[&str] = &["flight", "hotas", "throttle", "rudder", "pedals"];
+// Words in the names of gamepads.
+const GAMEPAD: &[&str] = &["pad", "controller", "xbox", "playstation"];
+
+/// A guessed mapping.
+pub(crate) struct Guess {
+    pub(crate) type_: char,
+    pub(crate) maps: Vec<(Input, Map)>,
+}
+
+struct Guesser<'a> {
+    caps: &'a Capabilities,
+    maps: Vec<(Input, Map)>,
+}
+
+impl Guesser<'_> {
+    // Get the range of an input, if the controller has it.
+    fn get(&self, event: Event) -> Option<Option<(i32, i32)>> {
+        let code = event.code();
+        self.caps
+            .inputs()
+            .find(|(e, _)| e.code() == code)
+            .map(|(_, range)| range)
+    }
+
+    fn has(&self, event: Event) -> bool {
+        self.get(event).is_some()
+    }
+
+    // Remap `from` to `to`, if the controller has `from`.
+    fn map(&mut self, from: Event, to: Event) {
+        let (Some(range), Some(code), Some(out)) =
+            (self.get(from), from.code(), to.code())
+        else {
+            return;
+        };
+        let mut map = Map::new(out);
+        if let Some((min, max)) = range.filter(|_| from.is_axis()) {
+            (map.min, map.max) = (min, max);
+        }
+        self.maps.push((Input::Code(code), map));
+    }
+}
+
+// Guess the kind of gamepad from its name.
+fn gamepad_type(name: &str) -> char {
+    let has = |words: &[&str]| words.iter().any(|w| name.contains(w));
+    if has(&["xbox", "x-box", "xinput"]) {
+        'x'
+    } else if has(&["playstation", "dualshock", "dualsense", "ps3", "ps4"]) {
+        'p'
+    } else if has(&["nintendo", "switch", "joy-con", "wii"]) {
+        'n'
+    } else {
+        'g'
+    }
+}
+
+/// Guess a mapping for a controller.
+pub(crate) fn guess(name: &str, caps: &Capabilities) -> Guess {
+    use Event::*;
+
+    let name = name.to_lowercase();
+    let mut guesser = Guesser {
+        caps,
+        maps: Vec::new(),
+    };
+    let g = &mut guesser;
+
+    // Gamepads with the kernel's standard layout have an A button, others
+    // only have generic joystick buttons.
+    let standard = g.has(ActionA(false));
+    let generic = g.has(Trigger(false));
+    let flight = FLIGHT.iter().any(|w| name.contains(w))
+        || g.has(Throttle(0.0))
+        || g.has(Rudder(0.0));
+    let sticks = g.has(JoyX(0.0))
+        && g.has(JoyY(0.0))
+        && ((g.has(CamX(0.0)) && g.has(CamY(0.0)))
+            || (g.has(JoyZ(0.0)) && g.has(CamZ(0.0))));
+    let gamepad = standard
+        || (generic
+            && !flight
+            && (sticks || GAMEPAD.iter().any(|w| name.contains(w))));
+    if !gamepad {
+        let type_ = if flight && generic { 'f' } else { 'w' };
+        return Guess {
+            type_,
+            maps: Vec::new(),
+        };
+    }
+
+    // Triggers are on `ABS_Z` and `ABS_RZ` when the right stick is on
+    // `ABS_RX` and `ABS_RY`, otherwise `ABS_Z` and `ABS_RZ` are the right
+    // stick.
+    if g.has(CamX(0.0)) && g.has(CamY(0.0)) {
+        g.map(JoyZ(0.0), TriggerL(0.0));
+        g.map(CamZ(0.0), TriggerR(0.0));
+    } else {
+        g.map(JoyZ(0.0), CamX(0.0));
+        g.map(CamZ(0.0), CamY(0.0));
+    }
+    // Or on `ABS_BRAKE` and `ABS_GAS`.
+    g.map(Brake(0.0), TriggerL(0.0));
+    g.map(Gas(0.0), TriggerR(0.0));
+
+    // Hats are the d-pad, unless it has d-pad buttons.
+    if ![Up, Down, Left, Right]
+        .iter()
+        .any(|dpad| g.has(dpad(false)))
+    {
+        g.map(PovUp(false), Up(false));
+        g.map(PovDown(false), Down(false));
+        g.map(PovLeft(false), Left(false));
+        g.map(PovRight(false), Right(false));
+    }
+
+    // Generic joystick buttons, in the order most gamepads number them.
+    if !standard {
+        g.map(Trigger(false), ActionA(false));
+        g.map(ActionM(false), ActionB(false));
+        g.map(Bumper(false), ActionH(false));
+        g.map(ActionR(false), ActionV(false));
+        g.map(ActionL(false), BumperL(false));
+        g.map(Pinky(false), BumperR(false));
+        // With 12 buttons, the first two numbered buttons are the triggers.
+        let numbered: &[Event] = if g.has(Number(6, false)) {
+            &[TriggerL(0.0), TriggerR(0.0), MenuL(false), MenuR(false)]
+        } else {
+            &[MenuL(false), MenuR(false), Exit(false)]
+        };
+        let sticks = [Joy(false), Cam(false)];
+        for (n, event) in (1..).zip(numbered.iter().chain(&sticks)) {
+            g.map(Number(n, false), *event);
+        }
+    }
+
+    Guess {
+        type_: gamepad_type(&name),
+        maps: guesser.maps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Event::*;
+
+    const STICK: Option<(i32, i32)> = Some((-32768, 32767));
+    const TRIGGER: Option<(i32, i32)> = Some((0, 255));
+
+    fn caps(inputs: &[(Event, Option<(i32, i32)>)]) -> Capabilities {
+        let mut caps = Capabilities::default();
+        for (event, range) in inputs {
+            caps.input(*event, *range);
+        }
+        caps
+    }
+
+    // Get the code an input is mapped to, and the range, if it's mapped.
+    fn mapping(guessed: &Guess, from: Event) -> Option<(u8, i32, i32)> {
+        let input = Input::Code(from.code()?);
+        let (_, map) = guessed.maps.iter().find(|(i, _)| *i == input)?;
+        Some((map.out, map.min, map.max))
+    }
+
+    // Get the code an input is mapped to, if it's mapped.
+    fn mapped(guessed: &Guess, from: Event) -> Option<u8> {
+        mapping(guessed, from).map(|(out, _, _)| out)
+    }
+
+    const HATS: [(Event, Option<(i32, i32)>); 4] = [
+        (PovUp(false), None),
+        (PovDown(false), None),
+        (PovLeft(false), None),
+        (PovRight(false), None),
+    ];
+
+    #[test]
+    fn standard_gamepad() {
+        let mut inputs = vec![
+            (ActionA(false), None),
+            (ActionB(false), None),
+            (JoyX(0.0), STICK),
+            (JoyY(0.0), STICK),
+            (CamX(0.0), STICK),
+            (CamY(0.0), STICK),
+            (JoyZ(0.0), TRIGGER),
+            (CamZ(0.0), TRIGGER),
+        ];
+        inputs.extend(HATS);
+        let guessed = guess("Xbox Wireless Controller", &caps(&inputs));
+        assert_eq!(guessed.type_, 'x');
+        // Triggers keep their range
+        let trigger = mapping(&guessed, JoyZ(0.0));
+        assert_eq!(trigger, Some((TriggerL(0.0).code().unwrap(), 0, 255)));
+        assert_eq!(mapped(&guessed, CamZ(0.0)), TriggerR(0.0).code());
+        assert_eq!(mapped(&guessed, PovLeft(false)), Left(false).code());
+        // Buttons already have the standard layout
+        assert_eq!(mapped(&guessed, ActionA(false)), None);
+    }
+
+    #[test]
+    fn generic_gamepad() {
+        let mut inputs = vec![
+            (Trigger(false), None),
+            (ActionM(false), None),
+            (Bumper(false), None),
+            (ActionR(false), None),
+            (ActionL(false), None),
+            (Pinky(false), None),
+            (JoyX(0.0), STICK),
+            (JoyY(0.0), STICK),
+            (JoyZ(0.0), STICK),
+            (CamZ(0.0), STICK),
+            (Up(false), None),
+        ];
+        inputs.extend((1..=6).map(|n| (Number(n, false), None)));
+        inputs.extend(HATS);
+        let guessed = guess("Generic USB Joystick", &caps(&inputs));
+        assert_eq!(guessed.type_, 'g');
+        assert_eq!(mapped(&guessed, Trigger(false)), ActionA(false).code());
+        assert_eq!(mapped(&guessed, Pinky(false)), BumperR(false).code());
+        // With 12 buttons, the first numbered buttons are the triggers
+        let trigger = mapped(&guessed, Number(1, false));
+        assert_eq!(trigger, TriggerL(0.0).code());
+        assert_eq!(mapped(&guessed, Number(6, false)), Cam(false).code());
+        // The right stick is on `ABS_Z` and `ABS_RZ`
+        assert_eq!(mapped(&guessed, CamZ(0.0)), CamY(0.0).code());
+        // It has d-pad buttons, so the hat isn't the d-pad
+        assert_eq!(mapped(&guessed, PovUp(false)), None);
+
+        // With fewer buttons, the numbered buttons start with the menu
+        inputs.retain(|(e, _)| !matches!(e, Number(5 | 6, _)));
+        let guessed = guess("Generic USB Gamepad", &caps(&inputs));
+        assert_eq!(guessed.type_, 'g');
+        let menu = mapped(&guessed, Number(1, false));
+        assert_eq!(menu, MenuL(false).code());
+        assert_eq!(mapped(&guessed, Number(4, false)), Joy(false).code());
+    }
+
+    #[test]
+    fn not_gamepads() {
+        let flight = [(Trigger(false), None), (Throttle(0.0), TRIGGER)];
+        let guessed = guess("Joystick", &caps(&flight));
+        assert_eq!(guessed.type_, 'f');
+        assert!(guessed.maps.is_empty());
+
+        // Gamepad words don't make a flightstick a gamepad
+        let flight = [(Trigger(false), None), (JoyX(0.0), STICK)];
+        let guessed = guess("HOTAS Flight Controller", &caps(&flight));
+        assert_eq!(guessed.type_, 'f');
+
+        // Buttons that aren't a joystick's
+        let keys = [(Exit(false), None), (Number(1, false), None)];
+        let guessed = guess("Remote Control", &caps(&keys));
+        assert_eq!(guessed.type_, 'w');
+        assert!(guessed.maps.is_empty());
+    }
+}
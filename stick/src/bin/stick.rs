@@ -0,0 +1,223 @@
+//! Inspect controllers, like `evtest` but in stick's terms.
+//!
+//! Requires the `cli` feature (`cargo install stick --features cli`).
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use stick::{Capabilities, Controller, Event, Listener};
+
+fn print_help() {
+    eprintln!("Usage: stick [COMMAND]");
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!();
+    eprintln!("list            List controllers and their inputs (default)");
+    eprintln!("watch [N]       Show raw and stick events from controller N");
+    eprintln!("rumble [N]      Test rumble on controller N");
+    eprintln!("--help          Print this help text");
+}
+
+/// Resolves to the controllers that are already plugged in.
+struct Plugged(Listener, Vec<Controller>);
+
+impl Future for Plugged {
+    type Output = Vec<Controller>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Vec<Controller>> {
+        while let Poll::Ready(controller) = Pin::new(&mut self.0).poll(cx) {
+            self.1.push(controller);
+        }
+        for error in self.0.take_errors() {
+            eprintln!("{}", error);
+        }
+        Poll::Ready(std::mem::take(&mut self.1))
+    }
+}
+
+// Print a list, if it's not empty.
+fn print_list(label: &str, list: Vec<String>) {
+    if !list.is_empty() {
+        println!("    {}: {}", label, list.join(", "));
+    }
+}
+
+fn print_capabilities(caps: &Capabilities) {
+    print_list(
+        "Buttons",
+        caps.buttons().iter().map(|e| e.name().into()).collect(),
+    );
+    print_list(
+        "Axes",
+        caps.axes()
+            .iter()
+            .map(|a| {
+                format!("{} ({} to {})", a.event().name(), a.min(), a.max())
+            })
+            .collect(),
+    );
+    print_list(
+        "Hats",
+        caps.hats().iter().map(|e| e.name().into()).collect(),
+    );
+    print_list(
+        "Numbered buttons",
+        caps.numbers().iter().map(|n| n.to_string()).collect(),
+    );
+    print_list(
+        "Raw",
+        caps.raw()
+            .iter()
+            .map(|a| match a.event() {
+                Event::Raw { kind, code, .. } => format!(
+                    "{:04X}:{:04X} ({} to {})",
+                    kind,
+                    code,
+                    a.min(),
+                    a.max(),
+                ),
+                event => event.name().to_string(),
+            })
+            .collect(),
+    );
+    if caps.contacts() != 0 {
+        println!("    Touch contacts: {}", caps.contacts());
+    }
+    let mut motion = Vec::new();
+    if caps.accelerometer() {
+        motion.push("Accelerometer".to_string());
+    }
+    if caps.gyroscope() {
+        motion.push("Gyroscope".to_string());
+    }
+    print_list("Motion", motion);
+    print_list(
+        "Haptic effects",
+        caps.effects().iter().map(|e| format!("{:?}", e)).collect(),
+    );
+}
+
+fn list(controllers: &[Controller]) {
+    if controllers.is_empty() {
+        println!("No controllers found");
+    }
+    for (i, controller) in controllers.iter().enumerate() {
+        println!(
+            "{}: {:016X} {:?}{} \"{}\"",
+            i,
+            controller.id(),
+            controller.kind(),
+            if controller.guessed() {
+                " (guessed)"
+            } else {
+                ""
+            },
+            controller.name(),
+        );
+        print_capabilities(&controller.capabilities());
+    }
+}
+
+async fn watch(mut controller: Controller) {
+    // Raw input is shown alongside, so don't also report it as unknown.
+    stick::silence_diagnostics();
+    controller.trace(true);
+    println!("{:<32}Stick", "Raw (type, code, value)");
+    loop {
+        let event = (&mut controller).await;
+        // Skip synchronization events.
+        let raw: Vec<String> = controller
+            .take_trace()
+            .into_iter()
+            .filter_map(|raw| match raw {
+                Event::Raw { kind: 0, .. } => None,
+                Event::Raw { kind, code, value } => {
+                    Some(format!("{:04X} {:04X} {}", kind, code, value))
+                }
+                _ => None,
+            })
+            .collect();
+        let rows = raw.len().max(1);
+        for row in 0..rows {
+            let raw = raw.get(row).map(String::as_str).unwrap_or("");
+            if row + 1 == rows {
+                println!("{:<32}{}", raw, event);
+            } else {
+                println!("{}", raw);
+            }
+        }
+        if let Event::Disconnect = event {
+            return;
+        }
+    }
+}
+
+fn rumble(mut controller: Controller) {
+    if !controller
+        .capabilities()
+        .effects()
+        .contains(&stick::Effect::Rumble)
+    {
+        println!("Rumble isn't supported by this controller");
+        return;
+    }
+    let steps = [
+        ("strong (left)", (1.0, 0.0)),
+        ("weak (right)", (0.0, 1.0)),
+        ("both at half power", (0.5, 0.5)),
+        ("both at full power", (1.0, 1.0)),
+    ];
+    for (label, power) in steps {
+        println!("Rumble: {}", label);
+        controller.rumble(power);
+        thread::sleep(Duration::from_secs(1));
+        controller.rumble(0.0);
+        thread::sleep(Duration::from_millis(250));
+    }
+    println!("Done");
+}
+
+async fn run(args: Vec<String>) {
+    let command = args.first().map(String::as_str).unwrap_or("list");
+    if !matches!(command, "list" | "watch" | "rumble") {
+        print_help();
+        return;
+    }
+    let index = match args.get(1).map(|n| n.parse::<usize>()) {
+        Some(Ok(index)) => index,
+        Some(Err(_)) => {
+            print_help();
+            return;
+        }
+        None => 0,
+    };
+
+    let mut controllers = Plugged(Listener::default(), Vec::new()).await;
+    if command == "list" {
+        list(&controllers);
+        return;
+    }
+    if index >= controllers.len() {
+        eprintln!("No controller {} (run `stick list`)", index);
+        return;
+    }
+    let controller = controllers.swap_remove(index);
+    println!("{:016X} \"{}\"", controller.id(), controller.name());
+    match command {
+        "watch" => watch(controller).await,
+        _ => rumble(controller),
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect();
+    pasts::block_on(run(args));
+}
@@ -0,0 +1,232 @@
+use crate::Event;
+
+/// A type of haptic effect that a [`Controller`](crate::Controller) supports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Effect {
+    /// Stereo rumble (used by [`Controller::rumble()`](crate::Controller))
+    Rumble,
+    /// Periodic waveform
+    Periodic,
+    /// Constant force
+    Constant,
+    /// Spring condition
+    Spring,
+    /// Friction condition
+    Friction,
+    /// Damper condition
+    Damper,
+    /// Inertia condition
+    Inertia,
+    /// Ramp force
+    Ramp,
+    /// Square waveform (periodic)
+    Square,
+    /// Triangle waveform (periodic)
+    Triangle,
+    /// Sine waveform (periodic)
+    Sine,
+    /// Rising sawtooth waveform (periodic)
+    SawUp,
+    /// Falling sawtooth waveform (periodic)
+    SawDown,
+    /// Custom waveform (periodic)
+    Custom,
+    /// Adjustable overall effect strength
+    Gain,
+    /// Adjustable autocenter strength
+    Autocenter,
+}
+
+/// An axis that a [`Controller`](crate::Controller) can produce, and the range
+/// of raw values the hardware reports for it.
+#[derive(Debug, Copy, Clone)]
+pub struct Axis {
+    event: Event,
+    min: i32,
+    max: i32,
+}
+
+impl Axis {
+    /// Get the event (with a neutral value) produced by this axis.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Get the minimum raw value reported by the hardware.
+    pub fn min(&self) -> i32 {
+        self.min
+    }
+
+    /// Get the maximum raw value reported by the hardware.
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+}
+
+/// The kinds of [`Event`]s a [`Controller`](crate::Controller) can produce,
+/// and the haptic effects it supports.
+///
+/// Events are listed with a neutral value (released / `0.0`).
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    buttons: Vec<Event>,
+    axes: Vec<Axis>,
+    hats: Vec<Event>,
+    numbers: Vec<i8>,
+    raw: Vec<Axis>,
+    contacts: u8,
+    accelerometer: bool,
+    gyroscope: bool,
+    effects: Vec<Effect>,
+}
+
+impl Capabilities {
+    /// Get the buttons that can be pressed.
+    pub fn buttons(&self) -> &[Event] {
+        &self.buttons
+    }
+
+    /// Get the axes (including triggers and throttles) that can be moved.
+    pub fn axes(&self) -> &[Axis] {
+        &self.axes
+    }
+
+    /// Get the hat switch directions that can be pressed.
+    pub fn hats(&self) -> &[Event] {
+        &self.hats
+    }
+
+    /// Get the numbered buttons that can be pressed.
+    pub fn numbers(&self) -> &[i8] {
+        &self.numbers
+    }
+
+    /// Get the inputs stick doesn't recognize (as [`Event::Raw`]), with their
+    /// raw ranges (`0` to `1` for digital inputs).
+    pub fn raw(&self) -> &[Axis] {
+        &self.raw
+    }
+
+    /// Get the number of fingers the touchpad can track at once (`0` if there
+    /// is no touchpad).
+    pub fn contacts(&self) -> u8 {
+        self.contacts
+    }
+
+    /// Check if there's an accelerometer ([`Event::Accel`]).
+    pub fn accelerometer(&self) -> bool {
+        self.accelerometer
+    }
+
+    /// Check if there's a gyroscope ([`Event::Gyro`]).
+    pub fn gyroscope(&self) -> bool {
+        self.gyroscope
+    }
+
+    /// Get the haptic effects that are supported.
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    /// Add an input, sorting it into the correct category.  `range` is the raw
+    /// hardware range, and should be `None` for digital inputs.
+    pub(crate) fn input(&mut self, event: Event, range: Option<(i32, i32)>) {
+        let code = event.code();
+        match event {
+            Event::Disconnect => {}
+            Event::Raw { kind, code, .. } => {
+                if !self.raw.iter().any(|a| {
+                    matches!(a.event, Event::Raw { kind: k, code: c, .. }
+                        if k == kind && c == code)
+                }) {
+                    let (min, max) = range.unwrap_or((0, 1));
+                    self.raw.push(Axis { event, min, max });
+                }
+            }
+            Event::Touch(n, _) => {
+                self.contacts = self.contacts.max(n.saturating_add(1))
+            }
+            Event::TouchX(..) | Event::TouchY(..) => {}
+            Event::Accel { .. } => self.accelerometer = true,
+            Event::Gyro { .. } => self.gyroscope = true,
+            Event::Number(n, _) => {
+                if !self.numbers.contains(&n) {
+                    self.numbers.push(n);
+                }
+            }
+            event if event.is_axis() => {
+                if !self.axes.iter().any(|a| a.event.code() == code) {
+                    let (min, max) = range.unwrap_or((0, 1));
+                    self.axes.push(Axis { event, min, max });
+                }
+            }
+            event => {
+                let list = if is_hat(event) {
+                    &mut self.hats
+                } else {
+                    &mut self.buttons
+                };
+                if !list.iter().any(|e| e.code() == code) {
+                    list.push(event);
+                }
+            }
+        }
+    }
+
+    /// Add a supported haptic effect.
+    pub(crate) fn effect(&mut self, effect: Effect) {
+        if !self.effects.contains(&effect) {
+            self.effects.push(effect);
+        }
+    }
+
+    /// Iterate over all inputs, with their raw ranges (if analog).
+    pub(crate) fn inputs(
+        &self,
+    ) -> impl Iterator<Item = (Event, Option<(i32, i32)>)> + '_ {
+        let buttons = self.buttons.iter().chain(self.hats.iter());
+        let buttons = buttons.map(|e| (*e, None));
+        let numbers = self
+            .numbers
+            .iter()
+            .map(|n| (Event::Number(*n, false), None));
+        let axes = self.axes.iter().chain(self.raw.iter());
+        let axes = axes.map(|a| (a.event, Some((a.min, a.max))));
+        let touches =
+            (0..self.contacts).map(|n| (Event::Touch(n, false), None));
+        let (x, y, z) = (0.0, 0.0, 0.0);
+        let accel =
+            Some(Event::Accel { x, y, z }).filter(|_| self.accelerometer);
+        let gyro = Some(Event::Gyro { x, y, z }).filter(|_| self.gyroscope);
+        let motion = accel.into_iter().chain(gyro).map(|e| (e, None));
+        buttons
+            .chain(numbers)
+            .chain(axes)
+            .chain(touches)
+            .chain(motion)
+    }
+}
+
+fn is_hat(event: Event) -> bool {
+    use Event::*;
+    matches!(
+        event,
+        PovUp(_)
+            | PovDown(_)
+            | PovLeft(_)
+            | PovRight(_)
+            | HatUp(_)
+            | HatDown(_)
+            | HatLeft(_)
+            | HatRight(_)
+            | TrimUp(_)
+            | TrimDown(_)
+            | TrimLeft(_)
+            | TrimRight(_)
+            | MicUp(_)
+            | MicDown(_)
+            | MicLeft(_)
+            | MicRight(_)
+    )
+}
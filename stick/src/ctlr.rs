@@ -1,150 +1,240 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Debug,
     future::Future,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, OnceLock},
     task::{Context, Poll},
+    time::Instant,
 };
 
-use crate::Event;
-
-#[repr(i8)]
-enum Btn {
-    Exit = 0,
-    MenuL = 1,
-    MenuR = 2,
-    ActionA = 3,
-    ActionB = 4,
-    ActionC = 5,
-    ActionH = 6,
-    ActionV = 7,
-    ActionD = 8,
-    Up = 9,
-    Down = 10,
-    Right = 11,
-    Left = 12,
-    BumperL = 13,
-    BumperR = 14,
-    Joy = 15,
-    Cam = 16,
-    PaddleLeft = 17,
-    PaddleRight = 18,
-    PinkyLeft = 19,
-    PinkyRight = 20,
-    Trigger = 21,
-    HatUp = 22,
-    HatDown = 23,
-    HatRight = 24,
-    HatLeft = 25,
-    MicUp = 26,
-    MicDown = 27,
-    MicRight = 28,
-    MicLeft = 29,
-    PovUp = 30,
-    PovDown = 31,
-    PovRight = 32,
-    PovLeft = 33,
-    MicPush = 34,
-    ActionL = 35,
-    ActionR = 36,
-    Bumper = 37,
-    ActionM = 38,
-    Pinky = 39,
-    PinkyForward = 40,
-    PinkyBackward = 41,
-    FlapsUp = 42,
-    FlapsDown = 43,
-    BoatForward = 44,
-    BoatBackward = 45,
-    AutopilotPath = 46,
-    AutopilotAlt = 47,
-    EngineMotorL = 48,
-    EngineMotorR = 49,
-    EngineFuelFlowL = 50,
-    EngineFuelFlowR = 51,
-    EngineIgnitionL = 52,
-    EngineIgnitionR = 53,
-    SpeedbrakeBackward = 54,
-    SpeedbrakeForward = 55,
-    ChinaBackward = 56,
-    ChinaForward = 57,
-    Apu = 58,
-    RadarAltimeter = 59,
-    LandingGearSilence = 60,
-    Eac = 61,
-    AutopilotToggle = 62,
-    ThrottleButton = 63,
-    Mouse = 64,
-    Scroll = 65,
-    Context = 66,
-    Dpi = 67,
-    TrimUp = 68,
-    TrimDown = 69,
-    TrimRight = 70,
-    TrimLeft = 71,
-}
+use crate::{
+    event::Analog,
+    focus::Focus,
+    raw::SdlInputs,
+    sdb::{self, Builtin, Input, Map, SdlInput},
+    Capabilities, Event,
+};
+
+// Built-in mappings, compiled from the databases by `build.rs` (sorted by
+// mask, then ID), and their mappings, shared once a controller matches them.
+#[cfg(all(feature = "sdb", target_os = "linux"))]
+const SDB: &[Builtin] = include!(concat!(env!("OUT_DIR"), "/remap_linux.rs"));
+#[cfg(all(feature = "sdb", target_os = "linux"))]
+static SDB_INFO: [OnceLock<Arc<Info>>; SDB.len()] =
+    [const { OnceLock::new() }; SDB.len()];
+#[cfg(all(feature = "gcdb", target_os = "linux"))]
+const GCDB: &[Builtin] = include!(concat!(env!("OUT_DIR"), "/sdlgc_linux.rs"));
+#[cfg(all(feature = "gcdb", target_os = "linux"))]
+static GCDB_INFO: [OnceLock<Arc<Info>>; GCDB.len()] =
+    [const { OnceLock::new() }; GCDB.len()];
+
+// A built-in database, and its shared mappings.
+type Database = (&'static [Builtin], &'static [OnceLock<Arc<Info>>]);
 
-#[repr(i8)]
-enum Axs {
-    TriggerL = 0,
-    TriggerR = 1,
-    JoyX = 2,
-    JoyY = 3,
-    JoyZ = 4,
-    CamX = 5,
-    CamY = 6,
-    CamZ = 7,
-    Wheel = 8,
-    Brake = 9,
-    Gas = 10,
-    Rudder = 11,
-    Slew = 12,
-    Throttle = 13,
-    ThrottleL = 14,
-    ThrottleR = 15,
-    Volume = 16,
-    MouseX = 17,
-    MouseY = 18,
-    ScrollX = 19,
-    ScrollY = 20,
-    ActionWheelX = 21,
-    ActionWheelY = 22,
-    Count, // Inferred correctly as long as it's last
+// Get the built-ins with IDs matching `id` (and their positions), from a table
+// sorted by mask, then ID.
+fn lookup(
+    builtins: &[Builtin],
+    id: u64,
+) -> impl Iterator<Item = (usize, &Builtin)> {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let mask = builtins.get(start)?.mask;
+        let group = start;
+        start += builtins[start..].partition_point(|b| b.mask == mask);
+        let found =
+            builtins[group..start].partition_point(|b| b.id < id & mask);
+        let matches = builtins[group + found..start]
+            .iter()
+            .take_while(move |b| b.id == id & mask);
+        Some(
+            matches
+                .enumerate()
+                .map(move |(i, b)| (group + found + i, b)),
+        )
+    })
+    .flatten()
 }
 
-#[derive(Debug)]
-struct Map {
-    deadzone: f64,
-    #[allow(dead_code)] // FIXME
-    scale: f64,
-    max: i32,
-    min: i32,
-    out: u8,
+impl Map {
+    // Whether the input needs converting to the output (rather than keeping
+    // its value).
+    fn converts(&self, input: Event) -> bool {
+        let out = Event::from_code(self.out, 0.0);
+        let analog = input.is_axis() || self.min != self.max;
+        let button = !analog && !matches!(input, Event::Raw { .. });
+        self.invert
+            || self.half != 0
+            || self.out_half != 0
+            || (analog && out.is_some_and(|out| out.analog().is_none()))
+            || (button && out.is_some_and(Event::is_axis))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Info {
-    #[allow(dead_code)] // FIXME
-    name: String,
-    maps: HashMap<u8, Map>,
-    #[allow(dead_code)] // FIXME
+    maps: HashMap<u8, Vec<Map>>,
+    // Remappings for platform-specific input stick doesn't recognize.
+    raw: HashMap<(u16, u16), Vec<Map>>,
+    // Remappings from SDL, resolved when the controller is connected.
+    sdl: Vec<(SdlInput, Map)>,
     type_: char,
+    // Whether the mapping was guessed, rather than from the database.
+    guess: bool,
+}
+
+impl Info {
+    // Get the remappings for an event.
+    fn get(&self, event: Event) -> &[Map] {
+        let maps = match event {
+            Event::Raw { kind, code, .. } => self.raw.get(&(kind, code)),
+            event => event.code().and_then(|code| self.maps.get(&code)),
+        };
+        maps.map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // Create from a controller's remappings.
+    fn new(type_: char, maps: &[(Input, Map)]) -> Self {
+        let mut info = Info {
+            type_,
+            ..Info::default()
+        };
+        for (input, map) in maps {
+            match *input {
+                Input::Code(code) => {
+                    info.maps.entry(code).or_default().push(*map)
+                }
+                Input::Raw(kind, code) => {
+                    info.raw.entry((kind, code)).or_default().push(*map)
+                }
+                Input::Sdl(sdl) => info.sdl.push((sdl, *map)),
+            }
+        }
+        info
+    }
+
+    // Add a remapping for an event.
+    fn insert(&mut self, event: Event, map: Map) {
+        let maps = match event {
+            Event::Raw { kind, code, .. } => {
+                self.raw.entry((kind, code)).or_default()
+            }
+            event => match event.code() {
+                Some(code) => self.maps.entry(code).or_default(),
+                None => return,
+            },
+        };
+        maps.push(map);
+    }
+
+    // Resolve the SDL remappings for a controller's inputs.
+    fn resolve(&self, inputs: &SdlInputs) -> Self {
+        let mut info = self.clone();
+        for (input, map) in &self.sdl {
+            let mut map = *map;
+            let event = match *input {
+                SdlInput::Button(i) => inputs.buttons.get(usize::from(i)),
+                SdlInput::Axis(i) => {
+                    inputs.axes.get(usize::from(i)).map(|(event, range)| {
+                        if map.min == map.max {
+                            (map.min, map.max) = *range;
+                        }
+                        event
+                    })
+                }
+                SdlInput::Hat(i, dir) => {
+                    let dir = match dir {
+                        1 => 0,
+                        2 => 1,
+                        4 => 2,
+                        _ => 3,
+                    };
+                    inputs.hats.get(usize::from(i)).map(|hat| &hat[dir])
+                }
+            };
+            if let Some(event) = event {
+                info.insert(*event, map);
+            }
+        }
+        info.sdl.clear();
+        info
+    }
 }
 
 impl Default for Info {
     fn default() -> Self {
         Self {
-            name: "Unknown".to_string(),
             maps: HashMap::new(),
+            raw: HashMap::new(),
+            sdl: Vec::new(),
             type_: 'w',
+            guess: false,
         }
     }
 }
 
+/// What kind of controller the mapping database says a controller is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Kind {
+    /// Xbox-style gamepad
+    Xbox,
+    /// PlayStation-style gamepad
+    PlayStation,
+    /// Nintendo-style gamepad
+    Nintendo,
+    /// Other gamepad
+    Gamepad,
+    /// Flightstick
+    Flight,
+    /// Not in the stick database
+    Unknown,
+}
+
+// A user mapping, and which controllers it matches.
+#[derive(Debug, Clone)]
+struct Rule {
+    id: u64,
+    // Which bits of the ID must match.
+    mask: u64,
+    priority: i8,
+    // Pattern the name must match.
+    pattern: Option<String>,
+    info: Arc<Info>,
+}
+
+impl Rule {
+    // Whether this is the same match as `other`.
+    fn same(&self, other: &Rule) -> bool {
+        (self.id, self.mask, self.priority, &self.pattern)
+            == (other.id, other.mask, other.priority, &other.pattern)
+    }
+}
+
 /// Controller remapping information
-#[derive(Debug)]
-pub struct Remap(HashMap<u64, Arc<Info>>);
+///
+/// Mappings are layered, from lowest to highest priority:
+///
+///  1. The built-in SDL mappings (`gcdb` feature)
+///  2. The built-in stick mappings (`sdb` feature)
+///  3. User mappings, from [`load()`](Remap::load)
+///  4. Runtime overrides of single inputs, from [`set()`](Remap::set)
+///
+/// Mappings can match any bus, vendor, product or version, and a pattern for
+/// the controller's name (see `sdb/README.md`).  A controller
+/// uses the matching mapping with the highest priority, then the most specific
+/// ID, then a name match, then the highest layer.
+///
+/// Cloning is cheap (the layers are shared until one of the clones is
+/// changed), so one mapping set can be shared by multiple listeners.
+#[derive(Debug, Clone)]
+pub struct Remap {
+    // User mappings, in the order they were loaded.
+    user: Arc<Vec<Rule>>,
+    // Runtime overrides of single inputs, by controller ID.
+    runtime: Arc<HashMap<u64, Vec<(Input, Map)>>>,
+}
 
 impl Default for Remap {
     fn default() -> Self {
@@ -153,112 +243,186 @@ impl Default for Remap {
 }
 
 impl Remap {
-    /// Create new remapper.
-    #[allow(unused_mut, clippy::let_and_return)]
+    /// Create new remapper, with the built-in mappings (depending on the
+    /// `sdb` and `gcdb` features).
     pub fn new() -> Self {
-        let mut remapper = Remap(HashMap::new());
-        #[cfg(all(feature = "gcdb", target_os = "linux"))]
-        {
-            let data = include_str!("../sdlgc_linux.sdb");
-            remapper = remapper.load(data).unwrap();
+        Remap {
+            user: Arc::new(Vec::new()),
+            runtime: Arc::new(HashMap::new()),
         }
-        #[cfg(all(feature = "sdb", target_os = "linux"))]
-        {
-            let data = include_str!("../remap_linux.sdb");
-            remapper = remapper.load(data).unwrap();
+    }
+
+    // Get the best mapping for a controller (without runtime overrides).
+    fn get(&self, id: u64, name: &str) -> Option<Arc<Info>> {
+        // Ranked by priority, then specificity, then name match, then layer.
+        let rank = |priority: i8, mask: u64, named: bool, layer: usize| {
+            (priority, mask.count_ones(), named, layer)
+        };
+        let mut best = None;
+        let mut choose = |rank, info| {
+            if best.as_ref().is_none_or(|(best, _)| rank >= *best) {
+                best = Some((rank, info));
+            }
+        };
+
+        // Built-in mappings, lowest layer first.
+        let builtins: &[Database] = &[
+            #[cfg(all(feature = "gcdb", target_os = "linux"))]
+            (GCDB, &GCDB_INFO),
+            #[cfg(all(feature = "sdb", target_os = "linux"))]
+            (SDB, &SDB_INFO),
+        ];
+        for (layer, (builtins, infos)) in builtins.iter().enumerate() {
+            for (i, builtin) in lookup(builtins, id) {
+                let pattern = builtin.pattern;
+                if pattern.is_none_or(|p| sdb::name_matches(p, name)) {
+                    let named = pattern.is_some();
+                    let rank =
+                        rank(builtin.priority, builtin.mask, named, layer);
+                    choose(rank, Err((builtin, &infos[i])));
+                }
+            }
+        }
+        // User mappings, in the layer above.
+        let layer = builtins.len();
+        for rule in self.user.iter() {
+            let pattern = rule.pattern.as_deref();
+            let matches = id & rule.mask == rule.id
+                && pattern.is_none_or(|p| sdb::name_matches(p, name));
+            if matches {
+                let named = pattern.is_some();
+                choose(rank(rule.priority, rule.mask, named, layer), Ok(rule));
+            }
         }
-        remapper
+
+        best.map(|(_, best)| match best {
+            Ok(rule) => Arc::clone(&rule.info),
+            Err((builtin, info)) => Arc::clone(info.get_or_init(|| {
+                Arc::new(Info::new(builtin.type_, builtin.maps))
+            })),
+        })
     }
 
-    /// Load a custom re-mapping.
-    pub fn load(mut self, data: &str) -> Option<Remap> {
-        // Controllers
-        for line in data.lines() {
-            let id = u64::from_str_radix(&line[..16], 16).ok()?;
-            let tab = line.find('\t')?;
-            let name = line[16..tab].to_string();
-            let type_ = line.get(tab + 1..tab + 2)?.chars().next()?;
-            let mut maps = HashMap::new();
-
-            // Events
-            for event in line.get(tab + 2..)?.split(';') {
-                let in_ = u8::from_str_radix(event.get(0..2)?, 16).ok()?;
-                let out = u8::from_str_radix(event.get(2..4)?, 16).ok()?;
-
-                // Tweaks
-                let mut cursor = 4;
-                let mut deadzone = f64::NAN;
-                let mut scale = f64::NAN;
-                let mut max: i32 = 0;
-                let mut min: i32 = 0;
-                while let Some(tweak) = event.get(cursor..)?.chars().next() {
-                    match tweak {
-                        'd' => {
-                            let end = event
-                                .get(cursor + 1..)?
-                                .find(char::is_lowercase)
-                                .unwrap_or(event.get(cursor + 1..)?.len());
-                            deadzone = event
-                                .get(cursor + 1..cursor + 1 + end)?
-                                .parse::<f64>()
-                                .ok()?;
-                            cursor += end + 1;
-                        }
-                        's' => {
-                            let end = event
-                                .get(cursor + 1..)?
-                                .find(char::is_lowercase)
-                                .unwrap_or(event.get(cursor + 1..)?.len());
-                            scale = event
-                                .get(cursor + 1..cursor + 1 + end)?
-                                .parse::<f64>()
-                                .ok()?
-                                .recip();
-                            cursor += end + 1;
-                        }
-                        'a' => {
-                            let end = event
-                                .get(cursor + 1..)?
-                                .find(char::is_lowercase)
-                                .unwrap_or(event.get(cursor + 1..)?.len());
-                            max = event
-                                .get(cursor + 1..cursor + 1 + end)?
-                                .parse::<i32>()
-                                .ok()?;
-                            cursor += end + 1;
-                        }
-                        'i' => {
-                            let end = event
-                                .get(cursor + 1..)?
-                                .find(char::is_lowercase)
-                                .unwrap_or(event.get(cursor + 1..)?.len());
-                            min = event
-                                .get(cursor + 1..cursor + 1 + end)?
-                                .parse::<i32>()
-                                .ok()?;
-                            cursor += end + 1;
-                        }
-                        _ => return None,
+    // Get the complete mapping for a connected controller.
+    fn info(&self, raw: &dyn crate::raw::Controller) -> Arc<Info> {
+        let mut info = self.get(raw.id(), raw.name()).unwrap_or_else(|| {
+            let guess = crate::auto::guess(raw.name(), &raw.capabilities());
+            let mut info = Info::new(guess.type_, &guess.maps);
+            info.guess = true;
+            Arc::new(info)
+        });
+        if !info.sdl.is_empty() {
+            info = Arc::new(info.resolve(&raw.sdl_inputs()));
+        }
+        if let Some(overrides) = self.runtime.get(&raw.id()) {
+            let info = Arc::make_mut(&mut info);
+            for (input, map) in overrides {
+                // Overrides are only set for stick's inputs.
+                match *input {
+                    Input::Code(code) => {
+                        info.maps.insert(code, vec![*map]);
                     }
+                    Input::Raw(kind, code) => {
+                        info.raw.insert((kind, code), vec![*map]);
+                    }
+                    Input::Sdl(_) => {}
                 }
-
-                maps.insert(
-                    in_,
-                    Map {
-                        deadzone,
-                        scale,
-                        max,
-                        min,
-                        out,
-                    },
-                );
             }
+        }
+        info
+    }
 
-            self.0.insert(id, Arc::new(Info { name, maps, type_ }));
+    /// Load custom re-mappings (in the mapping database format) into the user
+    /// layer, replacing the user mappings with the same matches.
+    ///
+    /// Returns `None` if a line is malformed or remaps to an unassigned code.
+    pub fn load(mut self, data: &str) -> Option<Remap> {
+        let user = Arc::make_mut(&mut self.user);
+        // Controllers
+        for line in data.lines() {
+            let line = sdb::parse(line)?;
+            // Outputs must be events (or `00` to drop the input).
+            for (_, map) in &line.maps {
+                Event::from_code(map.out, 0.0)?;
+            }
+            let rule = Rule {
+                id: line.id,
+                mask: line.mask,
+                priority: line.priority,
+                pattern: line.pattern.map(str::to_string),
+                info: Arc::new(Info::new(line.type_, &line.maps)),
+            };
+            user.retain(|r| !r.same(&rule));
+            user.push(rule);
         }
 
         Some(self)
     }
+
+    /// Override the mapping of one input for the controller with `id`, at
+    /// runtime, keeping the rest of its mapping.
+    ///
+    /// `input` is remapped to `output` (with any value, such as
+    /// `Event::ActionA(false)`), or dropped if `output` is
+    /// [`Event::Disconnect`] or has no [code](Event::code).  Inputs without a
+    /// code (other than [`Event::Raw`]) can't be remapped.
+    ///
+    /// ```
+    /// use stick::{Event, Remap};
+    ///
+    /// // Swap A and B on a wired Xbox 360 controller
+    /// let mut remap = Remap::new();
+    /// let id = 0x03005E048E021001;
+    /// remap.set(id, Event::ActionA(false), Event::ActionB(false));
+    /// remap.set(id, Event::ActionB(false), Event::ActionA(false));
+    /// ```
+    pub fn set(&mut self, id: u64, input: Event, output: Event) {
+        let input = match input {
+            Event::Raw { kind, code, .. } => Input::Raw(kind, code),
+            input => match input.code() {
+                Some(code) => Input::Code(code),
+                None => return,
+            },
+        };
+        let map = Map::new(output.code().unwrap_or(0x00));
+        let overrides = Arc::make_mut(&mut self.runtime).entry(id).or_default();
+        overrides.retain(|(i, _)| *i != input);
+        overrides.push((input, map));
+    }
+
+    /// Remove the user mappings for exactly the controller with `id` (whatever
+    /// its name) and its runtime overrides, so that it falls back to the
+    /// built-in mappings (or user mappings shared with other controllers).
+    pub fn remove(&mut self, id: u64) {
+        let exact = |rule: &Rule| rule.mask == u64::MAX && rule.id == id;
+        if self.user.iter().any(exact) {
+            Arc::make_mut(&mut self.user).retain(|rule| !exact(rule));
+        }
+        if self.runtime.contains_key(&id) {
+            Arc::make_mut(&mut self.runtime).remove(&id);
+        }
+    }
+
+    /// Merge `other` into this mapping set, layer by layer.  User mappings from
+    /// `other` replace the ones with the same matches, and its runtime
+    /// overrides replace the ones for the same inputs.
+    pub fn merge(&mut self, other: &Remap) {
+        if !other.user.is_empty() {
+            let user = Arc::make_mut(&mut self.user);
+            for rule in other.user.iter() {
+                user.retain(|r| !r.same(rule));
+                user.push(rule.clone());
+            }
+        }
+        for (id, overrides) in other.runtime.iter() {
+            let runtime = Arc::make_mut(&mut self.runtime);
+            let ours = runtime.entry(*id).or_default();
+            for (input, map) in overrides {
+                ours.retain(|(i, _)| i != input);
+                ours.push((*input, *map));
+            }
+        }
+    }
 }
 
 /// A gamepad, flightstick, or other controller.
@@ -267,12 +431,29 @@ pub struct Controller {
     remap: Arc<Info>,
     //
     raw: Box<dyn crate::raw::Controller>,
-    // Button states
+    // Button states, by event code
     btns: u128,
     // Number button states
     nums: u128,
-    // Axis states:
-    axis: [f64; Axs::Count as usize],
+    // Touch contact states
+    touches: u128,
+    // Axis states, by event code
+    axis: [f64; 0x80],
+    // Positive and negative halves of axes built from two inputs, by code
+    halves: HashMap<u8, [f64; 2]>,
+    // Focus state shared with the listener.
+    focus: Arc<Focus>,
+    // Whether events are currently enabled.
+    focused: bool,
+    // Whether exclusive access was requested.
+    grabbed: bool,
+    // Synthesized events from focus changes, and extra remapped events, in
+    // order.
+    pending: VecDeque<Event>,
+    // Unknown inputs already reported, by platform-specific kind and code
+    reported: HashSet<(u16, u16)>,
+    // When the last event was read.
+    time: Instant,
 }
 
 impl Debug for Controller {
@@ -289,17 +470,39 @@ impl Controller {
     ) -> Self {
         let btns = 0;
         let nums = 0;
-        let axis = [0.0; Axs::Count as usize];
-        let remap = remap.0.get(&raw.id()).cloned().unwrap_or_default();
+        let touches = 0;
+        let axis = [0.0; 0x80];
+        let halves = HashMap::new();
+        let remap = remap.info(&*raw);
+        let focus = Arc::new(Focus::default());
+        let focused = true;
+        let grabbed = false;
+        let pending = VecDeque::new();
+        let reported = HashSet::new();
+        let time = Instant::now();
         Self {
             remap,
             raw,
             btns,
             nums,
+            touches,
             axis,
+            halves,
+            focus,
+            focused,
+            grabbed,
+            pending,
+            reported,
+            time,
         }
     }
 
+    /// Share focus state with the listener this controller came from.
+    pub(crate) fn set_focus(&mut self, focus: Arc<Focus>) {
+        self.focused = focus.get();
+        self.focus = focus;
+    }
+
     /// Get a unique identifier for the specific model of gamepad.
     pub fn id(&self) -> u64 {
         self.raw.id()
@@ -310,6 +513,34 @@ impl Controller {
         self.raw.name()
     }
 
+    /// Get what kind of controller this is, according to the mapping
+    /// database.
+    pub fn kind(&self) -> Kind {
+        match self.remap.type_ {
+            'x' => Kind::Xbox,
+            'p' => Kind::PlayStation,
+            'n' => Kind::Nintendo,
+            'g' => Kind::Gamepad,
+            'f' => Kind::Flight,
+            _ => Kind::Unknown,
+        }
+    }
+
+    /// Whether this controller isn't in the mapping database, so its mapping
+    /// (and [`kind()`](Controller::kind)) was guessed from its capabilities
+    /// and name.
+    pub fn guessed(&self) -> bool {
+        self.remap.guess
+    }
+
+    /// Get when the last event was read from the controller (from the
+    /// platform's timestamps, where it has them), for timing motion events
+    /// with [`Fusion::process_at()`](crate::Fusion::process_at) and
+    /// [`Aim::process_at()`](crate::Aim::process_at).
+    pub fn time(&self) -> Instant {
+        self.time
+    }
+
     /// Turn on/off haptic force feedback.
     ///
     /// Takes either an `f32` for mono power or `(f32, f32)` for directional
@@ -322,40 +553,108 @@ impl Controller {
         self.raw.rumble(power.left(), power.right());
     }
 
-    fn button(&mut self, b: Btn, f: fn(bool) -> Event, p: bool) -> Poll<Event> {
-        let b = 1u128 << b as i8;
-        if (self.btns & b != 0) == p {
+    /// Grab (`true`) or release (`false`) exclusive access to this controller,
+    /// so that other processes (including the desktop) don't receive its
+    /// input.
+    ///
+    /// While the window is out of focus (see [`unfocus()`](crate::unfocus))
+    /// the grab is released, and it is restored on [`focus()`](crate::focus).
+    /// The grab is also released when the controller is dropped.  Returns
+    /// `false` if the grab failed or isn't supported on this platform.
+    pub fn grab(&mut self, grab: bool) -> bool {
+        if !self.focused {
+            // Grab is deferred until back in focus.
+            self.grabbed = grab;
+            return true;
+        }
+        let success = self.raw.grab(grab) || !grab;
+        self.grabbed = grab && success;
+        success
+    }
+
+    /// Start (`true`) or stop (`false`) recording the platform-specific input
+    /// read from this controller, before it's converted and remapped (for
+    /// debugging mappings).  Recorded input is kept until
+    /// [`take_trace()`](Controller::take_trace) is called.
+    pub fn trace(&mut self, enable: bool) {
+        self.raw.trace(enable);
+    }
+
+    /// Take the platform-specific input recorded since the last call, as
+    /// [`Event::Raw`]s (on Linux, every evdev event, including `EV_SYN`).
+    pub fn take_trace(&mut self) -> Vec<Event> {
+        self.raw.take_trace()
+    }
+
+    /// Get the kinds of events this controller can produce (after remapping),
+    /// and the haptic effects it supports.
+    pub fn capabilities(&self) -> Capabilities {
+        let raw = self.raw.capabilities();
+        let mut caps = Capabilities::default();
+        for (event, range) in raw.inputs() {
+            let maps = self.remap.get(event);
+            if maps.is_empty() {
+                caps.input(event, range);
+            }
+            for map in maps {
+                if let Some(event) = event.remap(map.out) {
+                    caps.input(event, range);
+                }
+            }
+        }
+        for effect in raw.effects() {
+            caps.effect(*effect);
+        }
+        caps
+    }
+
+    fn button(&mut self, code: u8, event: Event) -> Poll<Event> {
+        let b = 1u128 << code;
+        if (self.btns & b != 0) == (event.value() != 0.0) {
             Poll::Pending
         } else {
             self.btns ^= b;
-            Poll::Ready(f(p))
+            Poll::Ready(event)
         }
     }
 
-    fn number(
-        &mut self,
-        n: i8,
-        f: fn(i8, bool) -> Event,
-        p: bool,
-    ) -> Poll<Event> {
+    fn number(&mut self, n: i8, p: bool) -> Poll<Event> {
         let b = 1u128 << n;
         if (self.nums & b != 0) == p {
             Poll::Pending
         } else {
             self.nums ^= b;
-            Poll::Ready(f(n, p))
+            Poll::Ready(Event::Number(n, p))
+        }
+    }
+
+    fn touch(&mut self, n: u8, p: bool) -> Poll<Event> {
+        let Some(b) = 1u128.checked_shl(n.into()) else {
+            return Poll::Ready(Event::Touch(n, p));
+        };
+        if (self.touches & b != 0) == p {
+            Poll::Pending
+        } else {
+            self.touches ^= b;
+            Poll::Ready(Event::Touch(n, p))
+        }
+    }
+
+    // Relative motion isn't normalized, and repeats aren't filtered.
+    fn relative(&mut self, map: Option<&Map>, code: u8, v: f64) -> Poll<Event> {
+        let v = match map {
+            Some(map) if !map.scale.is_nan() => v * map.scale,
+            _ => v,
+        };
+        if v == 0.0 {
+            Poll::Pending
+        } else {
+            Poll::Ready(Event::from_code(code, v).unwrap())
         }
     }
 
     #[allow(clippy::float_cmp)] // imprecision should be consistent
-    fn axis(
-        &mut self,
-        ev: u8,
-        a: Axs,
-        f: fn(f64) -> Event,
-        v: f64,
-    ) -> Poll<Event> {
-        let map = self.remap.maps.get(&ev);
+    fn axis(&mut self, map: Option<&Map>, code: u8, v: f64) -> Poll<Event> {
         let v = if let Some(map) = map {
             let v = if map.min != 0 || map.max != 0 {
                 (((v - f64::from(map.min)) / f64::from(map.max - map.min))
@@ -373,24 +672,10 @@ impl Controller {
         } else {
             self.raw.axis(v).clamp(-1.0, 1.0)
         };
-        let axis = a as usize;
-        if self.axis[axis] == v {
-            Poll::Pending
-        } else {
-            self.axis[axis] = v;
-            Poll::Ready(f(v))
-        }
+        self.level(code, v)
     }
 
-    #[allow(clippy::float_cmp)] // imprecision should be consistent
-    fn pressure(
-        &mut self,
-        ev: u8,
-        a: Axs,
-        f: fn(f64) -> Event,
-        v: f64,
-    ) -> Poll<Event> {
-        let map = self.remap.maps.get(&ev);
+    fn pressure(&mut self, map: Option<&Map>, code: u8, v: f64) -> Poll<Event> {
         let v = if let Some(map) = map {
             let v = if map.min != 0 || map.max != 0 {
                 ((v - f64::from(map.min)) / f64::from(map.max - map.min))
@@ -406,177 +691,223 @@ impl Controller {
         } else {
             self.raw.pressure(v).clamp(0.0, 1.0)
         };
-        let axis = a as usize;
+        self.level(code, v)
+    }
+
+    // Update an axis or trigger, if it changed.
+    #[allow(clippy::float_cmp)] // imprecision should be consistent
+    fn level(&mut self, code: u8, v: f64) -> Poll<Event> {
+        let axis = usize::from(code);
         if self.axis[axis] == v {
             Poll::Pending
         } else {
             self.axis[axis] = v;
-            Poll::Ready(f(v))
+            Poll::Ready(Event::from_code(code, v).unwrap())
         }
     }
 
-    fn process(&mut self, event: Event) -> Poll<Event> {
-        // Do remapping step first.
-        let ev = event.to_id().0;
-        let event = if let Some(new_id) = self.remap.maps.get(&ev) {
-            let event = event.remap(new_id.out);
-            if matches!(event, Disconnect) {
-                return Poll::Pending;
-            }
-            event
+    // Convert an input to a different kind of output, using the halves and
+    // inversion from the remapping.
+    fn convert(&mut self, map: &Map, input: Event) -> Poll<Event> {
+        // Input position, from -1 to 1
+        let v = input.value();
+        let mut c = if map.min != map.max {
+            ((v - f64::from(map.min)) / f64::from(map.max - map.min)) * 2.0
+                - 1.0
         } else {
-            event
-        };
-        //
-        use Event::*;
-        match event {
-            Disconnect => Poll::Ready(Disconnect),
-            Exit(p) => self.button(Btn::Exit, Exit, p),
-            MenuL(p) => self.button(Btn::MenuL, MenuL, p),
-            MenuR(p) => self.button(Btn::MenuR, MenuR, p),
-            ActionA(p) => self.button(Btn::ActionA, ActionA, p),
-            ActionB(p) => self.button(Btn::ActionB, ActionB, p),
-            ActionC(p) => self.button(Btn::ActionC, ActionC, p),
-            ActionH(p) => self.button(Btn::ActionH, ActionH, p),
-            ActionV(p) => self.button(Btn::ActionV, ActionV, p),
-            ActionD(p) => self.button(Btn::ActionD, ActionD, p),
-            Up(p) => self.button(Btn::Up, Up, p),
-            Down(p) => self.button(Btn::Down, Down, p),
-            Right(p) => self.button(Btn::Right, Right, p),
-            Left(p) => self.button(Btn::Left, Left, p),
-            BumperL(p) => self.button(Btn::BumperL, BumperL, p),
-            BumperR(p) => self.button(Btn::BumperR, BumperR, p),
-            TriggerL(v) => self.pressure(ev, Axs::TriggerL, TriggerL, v),
-            TriggerR(v) => self.pressure(ev, Axs::TriggerR, TriggerR, v),
-            Joy(p) => self.button(Btn::Joy, Joy, p),
-            Cam(p) => self.button(Btn::Cam, Cam, p),
-            JoyX(v) => self.axis(ev, Axs::JoyX, JoyX, v),
-            JoyY(v) => self.axis(ev, Axs::JoyY, JoyY, v),
-            JoyZ(v) => self.axis(ev, Axs::JoyZ, JoyZ, v),
-            CamX(v) => self.axis(ev, Axs::CamX, CamX, v),
-            CamY(v) => self.axis(ev, Axs::CamY, CamY, v),
-            CamZ(v) => self.axis(ev, Axs::CamZ, CamZ, v),
-            PaddleLeft(p) => self.button(Btn::PaddleLeft, PaddleLeft, p),
-            PaddleRight(p) => self.button(Btn::PaddleRight, PaddleRight, p),
-            PinkyLeft(p) => self.button(Btn::PinkyLeft, PinkyLeft, p),
-            PinkyRight(p) => self.button(Btn::PinkyRight, PinkyRight, p),
-            Number(n, p) => self.number(n, Number, p),
-            HatUp(p) => self.button(Btn::HatUp, HatUp, p),
-            HatDown(p) => self.button(Btn::HatDown, HatDown, p),
-            HatRight(p) => self.button(Btn::HatRight, HatRight, p),
-            HatLeft(p) => self.button(Btn::HatLeft, HatLeft, p),
-            Trigger(p) => self.button(Btn::Trigger, Trigger, p),
-            MicUp(p) => self.button(Btn::MicUp, MicUp, p),
-            MicDown(p) => self.button(Btn::MicDown, MicDown, p),
-            MicRight(p) => self.button(Btn::MicRight, MicRight, p),
-            MicLeft(p) => self.button(Btn::MicLeft, MicLeft, p),
-            PovUp(p) => self.button(Btn::PovUp, PovUp, p),
-            PovDown(p) => self.button(Btn::PovDown, PovDown, p),
-            PovRight(p) => self.button(Btn::PovRight, PovRight, p),
-            PovLeft(p) => self.button(Btn::PovLeft, PovLeft, p),
-            Slew(v) => self.pressure(ev, Axs::Slew, Slew, v),
-            Throttle(v) => self.pressure(ev, Axs::Throttle, Throttle, v),
-            ThrottleL(v) => self.pressure(ev, Axs::ThrottleL, ThrottleL, v),
-            ThrottleR(v) => self.pressure(ev, Axs::ThrottleR, ThrottleR, v),
-            Volume(v) => self.pressure(ev, Axs::Volume, Volume, v),
-            Wheel(v) => self.pressure(ev, Axs::Wheel, Wheel, v),
-            Rudder(v) => self.pressure(ev, Axs::Rudder, Rudder, v),
-            Gas(v) => self.pressure(ev, Axs::Gas, Gas, v),
-            Brake(v) => self.pressure(ev, Axs::Brake, Brake, v),
-            MicPush(p) => self.button(Btn::MicPush, MicPush, p),
-            ActionL(p) => self.button(Btn::ActionL, ActionL, p),
-            ActionM(p) => self.button(Btn::ActionM, ActionM, p),
-            ActionR(p) => self.button(Btn::ActionR, ActionR, p),
-            Bumper(p) => self.button(Btn::Bumper, Bumper, p),
-            Pinky(p) => self.button(Btn::Pinky, Pinky, p),
-            PinkyForward(p) => self.button(Btn::PinkyForward, PinkyForward, p),
-            PinkyBackward(p) => {
-                self.button(Btn::PinkyBackward, PinkyBackward, p)
-            }
-            FlapsUp(p) => self.button(Btn::FlapsUp, FlapsUp, p),
-            FlapsDown(p) => self.button(Btn::FlapsDown, FlapsDown, p),
-            BoatForward(p) => self.button(Btn::BoatForward, BoatForward, p),
-            BoatBackward(p) => self.button(Btn::BoatBackward, BoatBackward, p),
-            AutopilotPath(p) => {
-                self.button(Btn::AutopilotPath, AutopilotPath, p)
-            }
-            AutopilotAlt(p) => self.button(Btn::AutopilotAlt, AutopilotAlt, p),
-            EngineMotorL(p) => self.button(Btn::EngineMotorL, EngineMotorL, p),
-            EngineMotorR(p) => self.button(Btn::EngineMotorR, EngineMotorR, p),
-            EngineFuelFlowL(p) => {
-                self.button(Btn::EngineFuelFlowL, EngineFuelFlowL, p)
+            match input.analog() {
+                None => v * 2.0 - 1.0,
+                Some(Analog::Axis) => self.raw.axis(v),
+                Some(Analog::Trigger) => self.raw.pressure(v) * 2.0 - 1.0,
+                Some(Analog::Relative) => v,
             }
-            EngineFuelFlowR(p) => {
-                self.button(Btn::EngineFuelFlowR, EngineFuelFlowR, p)
+        }
+        .clamp(-1.0, 1.0);
+        // (Keeping the centre as 0.0, rather than -0.0.)
+        if map.invert && c != 0.0 {
+            c = -c;
+        }
+        // Input pressure, from 0 to 1
+        let p = match map.half {
+            1 => c.max(0.0),
+            -1 => (-c).max(0.0),
+            _ => (c + 1.0) / 2.0,
+        };
+
+        let deadzone = |v: f64| {
+            if !map.deadzone.is_nan() && v.abs() <= map.deadzone {
+                0.0
+            } else {
+                v
             }
-            EngineIgnitionL(p) => {
-                self.button(Btn::EngineIgnitionL, EngineIgnitionL, p)
+        };
+        let out = Event::from_code(map.out, 0.0).unwrap_or(Event::Disconnect);
+        match out.analog() {
+            None => {
+                let pressed = f64::from(u8::from(p > 0.5));
+                match Event::from_code(map.out, pressed) {
+                    Some(event) => self.update(None, event),
+                    None => Poll::Pending,
+                }
             }
-            EngineIgnitionR(p) => {
-                self.button(Btn::EngineIgnitionR, EngineIgnitionR, p)
+            Some(Analog::Trigger) => self.level(map.out, deadzone(p)),
+            Some(Analog::Axis) if map.out_half != 0 => {
+                let halves = self.halves.entry(map.out).or_default();
+                halves[usize::from(map.out_half < 0)] = p;
+                let v = halves[0] - halves[1];
+                self.level(map.out, deadzone(v))
             }
-            SpeedbrakeBackward(p) => {
-                self.button(Btn::SpeedbrakeBackward, SpeedbrakeBackward, p)
+            Some(Analog::Axis) if map.half != 0 => {
+                self.level(map.out, deadzone(p * 2.0 - 1.0))
             }
-            SpeedbrakeForward(p) => {
-                self.button(Btn::SpeedbrakeForward, SpeedbrakeForward, p)
+            Some(Analog::Axis) => self.level(map.out, deadzone(c)),
+            Some(Analog::Relative) if c == 0.0 => Poll::Pending,
+            Some(Analog::Relative) => {
+                Poll::Ready(Event::from_code(map.out, c).unwrap())
             }
-            ChinaBackward(p) => {
-                self.button(Btn::ChinaBackward, ChinaBackward, p)
+        }
+    }
+
+    // Release all buttons and zero all axes, when focus is lost.
+    fn release(&mut self) {
+        if self.grabbed {
+            self.raw.grab(false);
+        }
+        for code in 0..0x80 {
+            let pressed = self.btns & (1 << code) != 0;
+            if pressed || self.axis[usize::from(code)] != 0.0 {
+                self.pending.extend(Event::from_code(code, 0.0));
             }
-            ChinaForward(p) => self.button(Btn::ChinaForward, ChinaForward, p),
-            Apu(p) => self.button(Btn::Apu, Apu, p),
-            RadarAltimeter(p) => {
-                self.button(Btn::RadarAltimeter, RadarAltimeter, p)
+        }
+        for n in 0..=i8::MAX {
+            if self.nums & (1 << n) != 0 {
+                self.pending.push_back(Event::Number(n, false));
             }
-            LandingGearSilence(p) => {
-                self.button(Btn::LandingGearSilence, LandingGearSilence, p)
+        }
+        for n in 0..128 {
+            if self.touches & (1 << n) != 0 {
+                self.pending.push_back(Event::Touch(n, false));
             }
-            Eac(p) => self.button(Btn::Eac, Eac, p),
-            AutopilotToggle(p) => {
-                self.button(Btn::AutopilotToggle, AutopilotToggle, p)
+        }
+        self.btns = 0;
+        self.nums = 0;
+        self.touches = 0;
+        self.axis = [0.0; 0x80];
+        self.halves.clear();
+    }
+
+    // Synchronize with the hardware state, when focus is regained.
+    fn resync(&mut self) {
+        if self.grabbed {
+            self.grabbed = self.raw.grab(true);
+        }
+        for event in self.raw.state() {
+            // Extra remapped events are queued after the first.
+            let first = self.pending.len();
+            if let Poll::Ready(event) = self.process(event) {
+                self.pending.insert(first, event);
             }
-            ThrottleButton(p) => {
-                self.button(Btn::ThrottleButton, ThrottleButton, p)
+        }
+    }
+
+    // Report input stick doesn't understand, once for each input.
+    fn report(&mut self, kind: u16, code: u16, value: i32) {
+        if self.reported.insert((kind, code)) {
+            let (id, name) = (self.raw.id(), self.raw.name());
+            crate::diag::report(id, name, kind, code, value);
+        }
+    }
+
+    fn process(&mut self, event: Event) -> Poll<Event> {
+        // Do remapping step first.
+        let remap = Arc::clone(&self.remap);
+        let maps = remap.get(event);
+        if maps.is_empty() {
+            if let Event::Raw { kind, code, value } = event {
+                self.report(kind, code, value);
             }
-            MouseX(v) => self.axis(ev, Axs::MouseX, MouseX, v),
-            MouseY(v) => self.axis(ev, Axs::MouseY, MouseY, v),
-            ScrollX(v) => self.axis(ev, Axs::ScrollX, ScrollX, v),
-            ScrollY(v) => self.axis(ev, Axs::ScrollY, ScrollY, v),
-            Mouse(p) => self.button(Btn::Mouse, Mouse, p),
-            Scroll(p) => self.button(Btn::Scroll, Scroll, p),
-            Context(p) => self.button(Btn::Context, Context, p),
-            Dpi(p) => self.button(Btn::Dpi, Dpi, p),
-            TrimUp(p) => self.button(Btn::TrimUp, TrimUp, p),
-            TrimDown(p) => self.button(Btn::TrimDown, TrimDown, p),
-            TrimLeft(p) => self.button(Btn::TrimLeft, TrimLeft, p),
-            TrimRight(p) => self.button(Btn::TrimRight, TrimRight, p),
-            ActionWheelX(v) => {
-                self.axis(ev, Axs::ActionWheelX, ActionWheelX, v)
+            return self.update(None, event);
+        }
+        // An input may map to several outputs, so queue all but the first.
+        let mut out = Poll::Pending;
+        for map in maps {
+            if map.out == 0x00 {
+                continue; // Disconnect
             }
-            ActionWheelY(v) => {
-                self.axis(ev, Axs::ActionWheelY, ActionWheelY, v)
+            let event = if map.converts(event) {
+                self.convert(map, event)
+            } else if let Some(event) = event.remap(map.out) {
+                self.update(Some(map), event)
+            } else {
+                continue; // Unassigned
+            };
+            if let Poll::Ready(event) = event {
+                if out.is_pending() {
+                    out = Poll::Ready(event);
+                } else {
+                    self.pending.push_back(event);
+                }
             }
         }
+        out
+    }
+
+    fn update(&mut self, map: Option<&Map>, event: Event) -> Poll<Event> {
+        use Event::*;
+        let code = match event {
+            Number(n, p) => return self.number(n, p),
+            Touch(n, p) => return self.touch(n, p),
+            Disconnect => return Poll::Ready(Disconnect),
+            event => match event.code() {
+                Some(code) => code,
+                None => return Poll::Ready(event),
+            },
+        };
+        let v = event.value();
+        match event.analog() {
+            None => self.button(code, event),
+            Some(Analog::Axis) => self.axis(map, code, v),
+            Some(Analog::Trigger) => self.pressure(map, code, v),
+            Some(Analog::Relative) => self.relative(map, code, v),
+        }
     }
 }
 
 impl Future for Controller {
     type Output = Event;
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Event> {
-        let mut this = self.as_mut();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Event> {
+        let this = self.get_mut();
 
-        if let Poll::Ready(event) = this.raw.poll(cx) {
-            let out = Self::process(&mut this, event);
-            if out.is_pending() {
-                Self::poll(self, cx)
+        // React to focus changes.
+        let focused = this.focus.poll(cx);
+        if focused != this.focused {
+            this.focused = focused;
+            if focused {
+                this.resync();
             } else {
-                out
+                this.release();
+            }
+        }
+        if let Some(event) = this.pending.pop_front() {
+            return Poll::Ready(event);
+        }
+
+        while let Poll::Ready(event) = this.raw.poll(cx) {
+            this.time = this.raw.time();
+            if matches!(event, Event::Disconnect) {
+                return Poll::Ready(event);
+            }
+            // Drop events while out of focus.
+            if !this.focused {
+                continue;
+            }
+            let out = this.process(event);
+            if out.is_ready() {
+                return out;
             }
-        } else {
-            Poll::Pending
         }
+        Poll::Pending
     }
 }
 
@@ -625,3 +956,238 @@ impl Rumble for (f32, f32) {
         self.1.clamp(0.0, 1.0)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // IDs that aren't in the built-in databases.
+    const PAD: u64 = 0x0300_ABCD_1234_0100;
+    const PAD_V2: u64 = 0x0300_ABCD_1234_0200;
+
+    // What the mapping a controller gets remaps `ActionA` (`02`) to, which
+    // tells the test mappings apart.
+    fn mapping(remap: &Remap, id: u64, name: &str) -> Option<u8> {
+        remap.get(id, name).map(|info| info.maps[&0x02][0].out)
+    }
+
+    // Poll a controller until it has no events ready, formatting the events.
+    pub(crate) fn events(controller: &mut Controller) -> Vec<String> {
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+        let mut events = Vec::new();
+        while let Poll::Ready(event) = Pin::new(&mut *controller).poll(&mut cx)
+        {
+            events.push(format!("{:?}", event));
+        }
+        events
+    }
+
+    #[test]
+    fn most_specific_id() {
+        let remap = Remap::new()
+            .load("0300ABCD1234****Any\tg0204\n0300ABCD12340100Exact\tg0205")
+            .unwrap();
+        assert_eq!(mapping(&remap, PAD, ""), Some(0x05));
+        assert_eq!(mapping(&remap, PAD_V2, ""), Some(0x04));
+        assert_eq!(mapping(&remap, 0x0300_ABCD_4321_0100, ""), None);
+    }
+
+    #[test]
+    fn priority_then_name() {
+        let remap = Remap::new()
+            .load(
+                "0300ABCD12340100Exact\tg0205\n\
+                0300ABCD1234****Named\tg0206\t0\t*pro*\n\
+                0300ABCD****0100Priority\tg0207\t1\tpriority*",
+            )
+            .unwrap();
+        // A higher priority wins over a more specific ID
+        let priority = mapping(&remap, PAD, "Priority Pad");
+        assert_eq!(priority, Some(0x07));
+        // A more specific ID wins over a name match
+        assert_eq!(mapping(&remap, PAD, "Pro Pad"), Some(0x05));
+        // A name match wins over an equally specific ID
+        let remap = remap.load("0300ABCD1234****Any\tg0204").unwrap();
+        let named = mapping(&remap, PAD_V2, "Pro Pad");
+        assert_eq!(named, Some(0x06));
+        assert_eq!(mapping(&remap, PAD_V2, "Pad"), Some(0x04));
+    }
+
+    #[cfg(all(feature = "sdb", target_os = "linux"))]
+    #[test]
+    fn user_over_builtin() {
+        let x360 = 0x0300_5E04_8E02_1001;
+        let remap = Remap::new();
+        let builtin = mapping(&remap, x360, "");
+        assert_eq!(builtin, Some(0x03));
+        let remap = remap.load("03005E048E021001Mine\tx0208").unwrap();
+        assert_eq!(mapping(&remap, x360, ""), Some(0x08));
+        // A wildcard user mapping is less specific than the built-in one
+        let remap = Remap::new().load("03005E048E02****Any\tx0204").unwrap();
+        assert_eq!(mapping(&remap, x360, ""), builtin);
+    }
+
+    #[test]
+    fn load() {
+        let mut remap = Remap::new()
+            .load("0300ABCD12340100Old\tg0209\n0300ABCD12340100New\tg020A")
+            .unwrap();
+        remap = remap.load("0300ABCD12340100Newer\tg020B").unwrap();
+        assert_eq!(remap.user.len(), 1);
+        assert_eq!(mapping(&remap, PAD, ""), Some(0x0B));
+
+        assert!(Remap::new().load("0300ABCD12340100Pad\tg02").is_none());
+        // Unassigned output
+        assert!(Remap::new().load("0300ABCD12340100Pad\tg0271").is_none());
+    }
+
+    #[test]
+    fn set_and_remove() {
+        let mut remap = Remap::new()
+            .load("0300ABCD12340100Exact\tg0205\n0300ABCD1234****Any\tg0204")
+            .unwrap();
+        remap.set(PAD, Event::ActionA(false), Event::ActionB(false));
+        remap.set(PAD, Event::ActionA(false), Event::ActionV(false));
+        remap.set(
+            PAD,
+            Event::Raw {
+                kind: 3,
+                code: 0x18,
+                value: 0,
+            },
+            Event::Brake(0.0),
+        );
+        let overrides = &remap.runtime[&PAD];
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(
+            overrides[0].0,
+            Input::Code(Event::ActionA(false).code().unwrap())
+        );
+        assert_eq!(overrides[0].1.out, Event::ActionV(false).code().unwrap());
+        assert_eq!(overrides[1].0, Input::Raw(3, 0x18));
+
+        // Only the exact mapping and overrides are removed
+        remap.remove(PAD);
+        assert!(remap.runtime.is_empty());
+        assert_eq!(mapping(&remap, PAD, ""), Some(0x04));
+        assert_eq!(mapping(&remap, PAD_V2, ""), Some(0x04));
+    }
+
+    #[test]
+    fn merge() {
+        let mut remap = Remap::new()
+            .load("0300ABCD12340100Ours\tg020C\n0300ABCD1234****Any\tg0204")
+            .unwrap();
+        remap.set(PAD, Event::ActionA(false), Event::ActionB(false));
+        remap.set(PAD, Event::ActionH(false), Event::ActionV(false));
+        let shared = remap.clone();
+
+        let mut other =
+            Remap::new().load("0300ABCD12340100Theirs\tg020D").unwrap();
+        other.set(PAD, Event::ActionA(false), Event::Exit(false));
+        remap.merge(&other);
+
+        assert_eq!(mapping(&remap, PAD, ""), Some(0x0D));
+        assert_eq!(mapping(&remap, PAD_V2, ""), Some(0x04));
+        let outs: Vec<u8> =
+            remap.runtime[&PAD].iter().map(|(_, m)| m.out).collect();
+        let code = |event: Event| event.code().unwrap();
+        assert_eq!(
+            outs,
+            [code(Event::ActionV(false)), code(Event::Exit(false))]
+        );
+
+        // Clones aren't changed
+        assert_eq!(mapping(&shared, PAD, ""), Some(0x0C));
+        assert_eq!(shared.runtime[&PAD].len(), 2);
+    }
+
+    #[test]
+    fn unfocus_releases() {
+        let raw = crate::raw::Mock::default();
+        let mut controller =
+            Controller::new(Box::new(raw.clone()), &Remap::new());
+        let focus = Arc::new(Focus::default());
+        controller.set_focus(Arc::clone(&focus));
+        raw.send([
+            Event::ActionA(true),
+            Event::JoyX(0.5),
+            Event::Number(3, true),
+            Event::Touch(0, true),
+            Event::ActionB(true),
+            Event::ActionB(false),
+        ]);
+        assert_eq!(events(&mut controller).len(), 6);
+
+        // Held inputs are released in order
+        focus.set(false);
+        assert_eq!(
+            events(&mut controller),
+            [
+                "ActionA(false)",
+                "JoyX(0.0)",
+                "Number(3, false)",
+                "Touch(0, false)"
+            ]
+        );
+        // Input is dropped while out of focus
+        raw.send([Event::ActionV(true)]);
+        assert!(events(&mut controller).is_empty());
+
+        // Regaining focus catches up with the hardware
+        raw.state
+            .lock()
+            .unwrap()
+            .extend([Event::ActionV(true), Event::JoyY(-1.0)]);
+        focus.set(true);
+        assert_eq!(events(&mut controller), ["ActionV(true)", "JoyY(-1.0)"]);
+    }
+
+    // Connect a mock controller with `PAD`'s ID, mapped by database `line`.
+    fn remapped(line: &str) -> (crate::raw::Mock, Controller) {
+        let raw = crate::raw::Mock {
+            id: PAD,
+            ..Default::default()
+        };
+        let remap = Remap::new().load(line).unwrap();
+        let controller = Controller::new(Box::new(raw.clone()), &remap);
+        (raw, controller)
+    }
+
+    #[test]
+    fn invert() {
+        let (raw, mut controller) = remapped("0300ABCD12340100Pad\tg2121v");
+        raw.send([Event::JoyY(0.5), Event::JoyY(-1.0), Event::JoyY(0.0)]);
+        assert_eq!(
+            events(&mut controller),
+            ["JoyY(-0.5)", "JoyY(1.0)", "JoyY(0.0)"],
+        );
+    }
+
+    #[test]
+    fn split_axis() {
+        // One axis for both triggers, like some older controllers
+        let line = "0300ABCD12340100Pad\tg220Eh-1;220Fh1";
+        let (raw, mut controller) = remapped(line);
+        raw.send([Event::JoyZ(-1.0)]);
+        assert_eq!(events(&mut controller), ["TriggerL(1.0)"]);
+        raw.send([Event::JoyZ(0.5)]);
+        assert_eq!(events(&mut controller), ["TriggerL(0.0)", "TriggerR(0.5)"],);
+        raw.send([Event::JoyZ(0.0)]);
+        assert_eq!(events(&mut controller), ["TriggerR(0.0)"]);
+    }
+
+    #[test]
+    fn combine_halves() {
+        // Triggers as the two halves of one axis, like a rudder
+        let line = "0300ABCD12340100Pad\tg0E20o-1;0F20o1";
+        let (raw, mut controller) = remapped(line);
+        raw.send([Event::TriggerL(1.0), Event::TriggerR(1.0)]);
+        assert_eq!(events(&mut controller), ["JoyX(-1.0)", "JoyX(0.0)"]);
+        raw.send([Event::TriggerL(0.0), Event::TriggerR(0.5)]);
+        assert_eq!(events(&mut controller), ["JoyX(1.0)", "JoyX(0.5)"]);
+        // Releasing both returns to the centre
+        raw.send([Event::TriggerR(0.0)]);
+        assert_eq!(events(&mut controller), ["JoyX(0.0)"]);
+    }
+}
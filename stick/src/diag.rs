@@ -0,0 +1,121 @@
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+type Hook = Arc<dyn Fn(&Diagnostic) + Send + Sync>;
+
+/// Where diagnostics go; `None` is the default (the `log` crate).
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// A report of input from a controller that stick doesn't understand.
+///
+/// The event type and code are platform-specific (on Linux, they are the evdev
+/// `EV_*` type and the matching `KEY_*` / `BTN_*` / `ABS_*` / `REL_*` / `MSC_*`
+/// code).
+///
+/// Each input is reported once for each controller, the first time it's
+/// seen, and not at all if the controller's mapping remaps it.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    id: u64,
+    name: String,
+    ev_type: u16,
+    code: u16,
+    value: i32,
+}
+
+impl Diagnostic {
+    /// Get the hardware identifier of the controller (same as
+    /// [`Controller::id()`](crate::Controller::id)).
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get the name of the controller.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the platform-specific event type.
+    pub fn ev_type(&self) -> u16 {
+        self.ev_type
+    }
+
+    /// Get the platform-specific event code.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Get the raw value of the event.
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown input from {} ({:016X}): type {}, code {}, value {}",
+            self.name, self.id, self.ev_type, self.code, self.value,
+        )
+    }
+}
+
+/// Set a function to be called for each [`Diagnostic`], instead of logging
+/// them as warnings with the `log` crate (the default).
+///
+/// The function may itself call `set_diagnostics()` or
+/// [`silence_diagnostics()`].
+pub fn set_diagnostics<F>(hook: F)
+where
+    F: Fn(&Diagnostic) + Send + Sync + 'static,
+{
+    *HOOK.write().unwrap() = Some(Arc::new(hook));
+}
+
+/// Ignore all [`Diagnostic`]s.
+pub fn silence_diagnostics() {
+    set_diagnostics(|_| {});
+}
+
+/// Report input that stick doesn't understand.
+pub(crate) fn report(id: u64, name: &str, ev_type: u16, code: u16, value: i32) {
+    let diagnostic = Diagnostic {
+        id,
+        name: name.to_string(),
+        ev_type,
+        code,
+        value,
+    };
+    // Called without the lock held, so the hook can replace itself.
+    let hook = HOOK.read().unwrap().clone();
+    match hook {
+        Some(hook) => hook(&diagnostic),
+        None => {
+            log::warn!("{}", diagnostic);
+            log::warn!("Report at https://github.com/ardaku/stick/issues");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn hook_can_replace_itself() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        set_diagnostics(|diagnostic| {
+            assert_eq!((diagnostic.ev_type(), diagnostic.code()), (3, 0x28));
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            silence_diagnostics();
+        });
+        report(0x0300_ABCD_1234_0100, "Pad", 3, 0x28, 1);
+        report(0x0300_ABCD_1234_0100, "Pad", 3, 0x28, 2);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+}
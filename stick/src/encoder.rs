@@ -0,0 +1,154 @@
+use crate::Event;
+
+/// Tracks the position of a dial, wheel or other rotary encoder from its
+/// relative events (such as [`Event::Dial`] or [`Event::ScrollY`]).
+///
+/// ```rust
+/// use stick::{Encoder, Event};
+///
+/// let mut dial = Encoder::new(Event::Dial);
+/// assert_eq!(dial.process(Event::Dial(2.0)), Some(2));
+/// assert_eq!(dial.process(Event::Dial(-1.0)), Some(-1));
+/// assert_eq!(dial.position(), 1);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Encoder {
+    // Which event is tracked.
+    code: Option<u8>,
+    // Units per detent.
+    detent: f64,
+    // Motion that hasn't added up to a detent yet.
+    remainder: f64,
+    // Absolute position, in detents.
+    position: i64,
+    // Number of detents in a full turn, to wrap around (0 doesn't wrap).
+    wrap: i64,
+}
+
+impl Encoder {
+    /// Track the events produced by `input`.
+    pub fn new(input: fn(f64) -> Event) -> Self {
+        Self {
+            code: input(0.0).code(),
+            detent: 1.0,
+            remainder: 0.0,
+            position: 0,
+            wrap: 0,
+        }
+    }
+
+    /// Set how much motion makes up one detent (default `1.0`), for devices
+    /// that report finer steps.
+    pub fn detent(mut self, detent: f64) -> Self {
+        self.detent = detent;
+        self
+    }
+
+    /// Wrap the position around after `detents` (so it stays from `0` to
+    /// `detents - 1`), such as for a dial that turns all the way around.
+    pub fn wrap(mut self, detents: u32) -> Self {
+        self.wrap = detents.into();
+        self.position = self.wrapped(self.position);
+        self
+    }
+
+    /// Update with an event from the controller, returning how many detents
+    /// the encoder moved (if any).  Other events are ignored.
+    pub fn process(&mut self, event: Event) -> Option<i64> {
+        let (code, value) = (event.code(), event.value());
+        if code.is_none() || code != self.code || !value.is_finite() {
+            return None;
+        }
+        self.remainder += value / self.detent;
+        let delta = self.remainder.trunc();
+        if delta == 0.0 {
+            return None;
+        }
+        self.remainder -= delta;
+        let delta = delta as i64;
+        self.position = self.wrapped(self.position.saturating_add(delta));
+        Some(delta)
+    }
+
+    /// Get the absolute position, in detents.
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    /// Set the absolute position, in detents.
+    pub fn set_position(&mut self, position: i64) {
+        self.position = self.wrapped(position);
+        self.remainder = 0.0;
+    }
+
+    fn wrapped(&self, position: i64) -> i64 {
+        if self.wrap == 0 {
+            position
+        } else {
+            position.rem_euclid(self.wrap)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractional_steps() {
+        // High-resolution wheels report 120 units per detent
+        let mut wheel = Encoder::new(Event::ScrollY).detent(120.0);
+        assert_eq!(wheel.process(Event::ScrollY(60.0)), None);
+        assert_eq!(wheel.process(Event::ScrollY(30.0)), None);
+        assert_eq!(wheel.process(Event::ScrollY(90.0)), Some(1));
+        assert_eq!(wheel.process(Event::ScrollY(240.0)), Some(2));
+        assert_eq!(wheel.position(), 3);
+        // What's left over carries on
+        assert_eq!(wheel.process(Event::ScrollY(60.0)), Some(1));
+    }
+
+    #[test]
+    fn reversal() {
+        let mut dial = Encoder::new(Event::Dial).detent(2.0);
+        assert_eq!(dial.process(Event::Dial(3.0)), Some(1));
+        // Turning back cancels the partial step first
+        assert_eq!(dial.process(Event::Dial(-1.0)), None);
+        assert_eq!(dial.process(Event::Dial(-2.0)), Some(-1));
+        assert_eq!(dial.process(Event::Dial(-4.0)), Some(-2));
+        assert_eq!(dial.position(), -2);
+    }
+
+    #[test]
+    fn wrap() {
+        let mut dial = Encoder::new(Event::Dial).wrap(4);
+        assert_eq!(dial.process(Event::Dial(-1.0)), Some(-1));
+        assert_eq!(dial.position(), 3);
+        assert_eq!(dial.process(Event::Dial(6.0)), Some(6));
+        assert_eq!(dial.position(), 1);
+        dial.set_position(-6);
+        assert_eq!(dial.position(), 2);
+        // Wrapping keeps the current position in range
+        let mut dial = Encoder::new(Event::Dial);
+        dial.set_position(10);
+        assert_eq!(dial.wrap(4).position(), 2);
+    }
+
+    #[test]
+    fn ignores_other_input() {
+        let mut dial = Encoder::new(Event::Dial);
+        assert_eq!(dial.process(Event::ScrollX(1.0)), None);
+        assert_eq!(dial.process(Event::ActionA(true)), None);
+        assert_eq!(dial.process(Event::Dial(f64::NAN)), None);
+        assert_eq!(dial.process(Event::Dial(f64::INFINITY)), None);
+        assert_eq!(dial.position(), 0);
+        assert_eq!(dial.process(Event::Dial(1.0)), Some(1));
+    }
+
+    #[test]
+    fn saturates() {
+        let mut dial = Encoder::new(Event::Dial);
+        dial.set_position(i64::MAX - 1);
+        assert_eq!(dial.process(Event::Dial(1e30)), Some(i64::MAX));
+        assert_eq!(dial.position(), i64::MAX);
+    }
+}
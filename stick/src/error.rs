@@ -0,0 +1,35 @@
+use std::{fmt, io};
+
+/// An error from the platform's controller APIs.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Couldn't start watching for controllers to be plugged in.
+    Listener(io::Error),
+    /// Couldn't set up a controller (includes the device's path).
+    Controller(String, io::Error),
+    /// Couldn't create or send to a virtual controller.
+    Output(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Listener(e) => write!(f, "Couldn't listen: {}", e),
+            Error::Controller(path, e) => {
+                write!(f, "Couldn't set up controller {}: {}", path, e)
+            }
+            Error::Output(e) => write!(f, "Virtual controller failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Listener(e) | Error::Controller(_, e) | Error::Output(e) => {
+                Some(e)
+            }
+        }
+    }
+}
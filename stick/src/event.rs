@@ -1,3 +1,29 @@
+/// A button that can sense how hard it's pressed, for [`Event::Pressure`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Button {
+    /// D-Pad Up
+    Up,
+    /// D-Pad Down
+    Down,
+    /// D-Pad Left
+    Left,
+    /// D-Pad Right
+    Right,
+    /// Left shoulder button
+    BumperL,
+    /// Right shoulder button
+    BumperR,
+    /// Action A
+    ActionA,
+    /// Action B
+    ActionB,
+    /// Action H
+    ActionH,
+    /// Action V
+    ActionV,
+}
+
 /// An event from a [`Controller`](crate::Controller).
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
@@ -192,9 +218,9 @@ pub enum Event {
     AutopilotToggle(bool),
     /// Throttle button (Left)
     ThrottleButton(bool),
-    /// Mouse delta position horizontal - between -1.0 and 1.0
+    /// Mouse motion horizontal (relative, in the device's units)
     MouseX(f64),
-    /// Mouse delta position vertical - between -1.0 and 1.0
+    /// Mouse motion vertical (relative, in the device's units)
     MouseY(f64),
     /// Mouse primary button
     Mouse(bool),
@@ -213,9 +239,9 @@ pub enum Event {
     Context(bool),
     /// DPI Button on a mouse
     Dpi(bool),
-    /// Scroll Wheel X on a mouse - between -1.0 and 1.0
+    /// Scroll Wheel X on a mouse (relative, in detents; positive is right)
     ScrollX(f64),
-    /// Scroll Wheel Y on a mouse - between -1.0 and 1.0
+    /// Scroll Wheel Y on a mouse (relative, in detents; positive is up)
     ScrollY(f64),
     /// Scroll Button on a mouse
     Scroll(bool),
@@ -223,219 +249,327 @@ pub enum Event {
     ActionWheelX(f64),
     /// Vertical axis under the action buttons - between -1.0 and 1.0
     ActionWheelY(f64),
+    /// Touchpad click (the whole touchpad is pushed down)
+    Touchpad(bool),
+    /// A finger touched (`true`) or left (`false`) the touchpad.  The contact
+    /// number stays the same for a finger until it's lifted.
+    Touch(u8, bool),
+    /// Touch contact position horizontal - between 0.0 (left) and 1.0 (right)
+    TouchX(u8, f64),
+    /// Touch contact position vertical - between 0.0 (top) and 1.0 (bottom)
+    TouchY(u8, f64),
+    /// How hard a pressure-sensitive button is pressed - between 0.0 and 1.0
+    /// (sent alongside the button's own press and release events)
+    Pressure(Button, f64),
+    /// Motion in and out (relative, in the device's units), such as pushing
+    /// or pulling the cap of a 3D mouse
+    MouseZ(f64),
+    /// Rotation around the horizontal axis (relative, in the device's units)
+    RotateX(f64),
+    /// Rotation around the vertical axis (relative, in the device's units)
+    RotateY(f64),
+    /// Rotation around the in and out axis (relative, in the device's units)
+    RotateZ(f64),
+    /// Dial or rotary encoder (relative, in detents)
+    Dial(f64),
+    /// Secondary dial or rotary encoder (relative, in detents)
+    Knob(f64),
+    /// Accelerometer reading in m/s² (includes gravity)
+    Accel {
+        /// Acceleration along the X axis
+        x: f64,
+        /// Acceleration along the Y axis
+        y: f64,
+        /// Acceleration along the Z axis
+        z: f64,
+    },
+    /// Gyroscope reading in rad/s
+    Gyro {
+        /// Angular velocity around the X axis
+        x: f64,
+        /// Angular velocity around the Y axis
+        y: f64,
+        /// Angular velocity around the Z axis
+        z: f64,
+    },
+    /// Input that stick doesn't recognize, with its platform-specific kind and
+    /// code (on Linux, the evdev `EV_*` type and code), and unprocessed value.
+    ///
+    /// These can be remapped to other events with [`Remap`](crate::Remap).
+    Raw {
+        /// Platform-specific kind of input
+        kind: u16,
+        /// Platform-specific input code
+        code: u16,
+        /// Unprocessed value
+        value: i32,
+    },
 }
 
-impl Event {
-    #[inline(always)]
-    pub(crate) fn remap(self, new_id: u8) -> Self {
-        Self::from_id(new_id, self.to_id().1)
-    }
+/// How an event's value is handled, for events that aren't buttons.
+#[derive(Copy, Clone)]
+pub(crate) enum Analog {
+    /// Centered, between -1.0 and 1.0
+    Axis,
+    /// Between 0.0 and 1.0
+    Trigger,
+    /// Relative motion, in the device's units
+    Relative,
+}
 
-    #[inline(always)]
-    fn from_id(id: u8, value: f64) -> Self {
-        match id {
-            0x00 => Event::Disconnect,
-            0x01 => Event::Exit(value != 0.0),
-            0x02 => Event::ActionA(value != 0.0),
-            0x03 => Event::ActionB(value != 0.0),
-            0x04 => Event::ActionC(value != 0.0),
-            0x05 => Event::ActionH(value != 0.0),
-            0x06 => Event::ActionV(value != 0.0),
-            0x07 => Event::ActionD(value != 0.0),
-            0x08 => Event::MenuL(value != 0.0),
-            0x09 => Event::MenuR(value != 0.0),
-            0x0A => Event::Joy(value != 0.0),
-            0x0B => Event::Cam(value != 0.0),
-            0x0C => Event::BumperL(value != 0.0),
-            0x0D => Event::BumperR(value != 0.0),
-            0x0E => Event::TriggerL(value),
-            0x0F => Event::TriggerR(value),
-            0x10 => Event::Up(value != 0.0),
-            0x11 => Event::Down(value != 0.0),
-            0x12 => Event::Left(value != 0.0),
-            0x13 => Event::Right(value != 0.0),
-            0x14 => Event::HatUp(value != 0.0),
-            0x15 => Event::HatDown(value != 0.0),
-            0x16 => Event::HatLeft(value != 0.0),
-            0x17 => Event::HatRight(value != 0.0),
-            0x18 => Event::MicUp(value != 0.0),
-            0x19 => Event::MicDown(value != 0.0),
-            0x1A => Event::MicLeft(value != 0.0),
-            0x1B => Event::MicRight(value != 0.0),
-            0x1C => Event::PovUp(value != 0.0),
-            0x1D => Event::PovDown(value != 0.0),
-            0x1E => Event::PovLeft(value != 0.0),
-            0x1F => Event::PovRight(value != 0.0),
-            0x20 => Event::JoyX(value),
-            0x21 => Event::JoyY(value),
-            0x22 => Event::JoyZ(value),
-            0x23 => Event::CamX(value),
-            0x24 => Event::CamY(value),
-            0x25 => Event::CamZ(value),
-            0x26 => Event::Slew(value),
-            0x27 => Event::Throttle(value),
-            0x28 => Event::ThrottleL(value),
-            0x29 => Event::ThrottleR(value),
-            0x2A => Event::Volume(value),
-            0x2B => Event::Wheel(value),
-            0x2C => Event::Rudder(value),
-            0x2D => Event::Gas(value),
-            0x2E => Event::Brake(value),
-            0x2F => Event::MicPush(value != 0.0),
-            0x30 => Event::Trigger(value != 0.0),
-            0x31 => Event::Bumper(value != 0.0),
-            0x32 => Event::ActionL(value != 0.0),
-            0x33 => Event::ActionM(value != 0.0),
-            0x34 => Event::ActionR(value != 0.0),
-            0x35 => Event::Pinky(value != 0.0),
-            0x36 => Event::PinkyForward(value != 0.0),
-            0x37 => Event::PinkyBackward(value != 0.0),
-            0x38 => Event::FlapsUp(value != 0.0),
-            0x39 => Event::FlapsDown(value != 0.0),
-            0x3A => Event::BoatForward(value != 0.0),
-            0x3B => Event::BoatBackward(value != 0.0),
-            0x3C => Event::AutopilotPath(value != 0.0),
-            0x3D => Event::AutopilotAlt(value != 0.0),
-            0x3E => Event::EngineMotorL(value != 0.0),
-            0x3F => Event::EngineMotorR(value != 0.0),
-            0x40 => Event::EngineFuelFlowL(value != 0.0),
-            0x41 => Event::EngineFuelFlowR(value != 0.0),
-            0x42 => Event::EngineIgnitionL(value != 0.0),
-            0x43 => Event::EngineIgnitionR(value != 0.0),
-            0x44 => Event::SpeedbrakeBackward(value != 0.0),
-            0x45 => Event::SpeedbrakeForward(value != 0.0),
-            0x46 => Event::ChinaBackward(value != 0.0),
-            0x47 => Event::ChinaForward(value != 0.0),
-            0x48 => Event::Apu(value != 0.0),
-            0x49 => Event::RadarAltimeter(value != 0.0),
-            0x4A => Event::LandingGearSilence(value != 0.0),
-            0x4B => Event::Eac(value != 0.0),
-            0x4C => Event::AutopilotToggle(value != 0.0),
-            0x4D => Event::ThrottleButton(value != 0.0),
-            0x4E => Event::MouseX(value),
-            0x4F => Event::MouseY(value),
-            0x50 => Event::Mouse(value != 0.0),
-            0x51 => Event::PaddleLeft(value != 0.0),
-            0x52 => Event::PaddleRight(value != 0.0),
-            0x53 => Event::PinkyLeft(value != 0.0),
-            0x54 => Event::PinkyRight(value != 0.0),
-            0x55 => Event::Context(value != 0.0),
-            0x56 => Event::Dpi(value != 0.0),
-            0x57 => Event::ScrollX(value),
-            0x58 => Event::ScrollY(value),
-            0x59 => Event::Scroll(value != 0.0),
-            0x5A => Event::TrimUp(value != 0.0),
-            0x5B => Event::TrimDown(value != 0.0),
-            0x5C => Event::TrimLeft(value != 0.0),
-            0x5D => Event::TrimRight(value != 0.0),
-            0x5E => Event::ActionWheelX(value),
-            0x5F => Event::ActionWheelY(value),
-            n => Event::Number((n & !0x80) as i8, value != 0.0),
+// Generate the conversions to and from codes in the mapping database.
+macro_rules! codes {
+    (
+        buttons { $($b_code:literal $b:ident,)* }
+        axes { $($a_code:literal $a:ident,)* }
+        triggers { $($t_code:literal $t:ident,)* }
+        relative { $($r_code:literal $r:ident,)* }
+        pressure { $($p_code:literal $p:ident,)* }
+    ) => {
+        impl Event {
+            /// Get the code for this event in the mapping database, which
+            /// never changes between versions.  Numbered buttons are `0x80`
+            /// plus the number, and `0x00` (`Disconnect`) disables an input.
+            ///
+            /// Returns `None` for events that can't be remapped (raw input,
+            /// touch contacts and motion).
+            ///
+            /// ```rust
+            /// use stick::Event;
+            ///
+            /// let code = Event::ActionA(true).code().unwrap();
+            /// let event = Event::from_code(code, 1.0).unwrap();
+            /// assert!(matches!(event, Event::ActionA(true)));
+            /// assert_eq!(event.name(), "ActionA");
+            /// ```
+            pub fn code(self) -> Option<u8> {
+                use Event::*;
+                Some(match self {
+                    Disconnect => 0x00,
+                    $($b(_) => $b_code,)*
+                    $($a(_) => $a_code,)*
+                    $($t(_) => $t_code,)*
+                    $($r(_) => $r_code,)*
+                    Pressure(button, _) => match button {
+                        $(Button::$p => $p_code,)*
+                    },
+                    Number(n, _) => n as u8 | 0x80,
+                    Raw { .. }
+                    | Touch(..)
+                    | TouchX(..)
+                    | TouchY(..)
+                    | Accel { .. }
+                    | Gyro { .. } => return None,
+                })
+            }
+
+            /// Get the event for a [`code()`](Event::code), with `value`
+            /// (buttons are pressed when it's not `0.0`).  Returns `None` if
+            /// the code isn't assigned to an event.
+            pub fn from_code(code: u8, value: f64) -> Option<Self> {
+                use Event::*;
+                let pushed = value != 0.0;
+                Some(match code {
+                    0x00 => Disconnect,
+                    $($b_code => $b(pushed),)*
+                    $($a_code => $a(value),)*
+                    $($t_code => $t(value),)*
+                    $($r_code => $r(value),)*
+                    $($p_code => Pressure(Button::$p, value),)*
+                    0x80..=0xFF => Number((code & !0x80) as i8, pushed),
+                    _ => return None,
+                })
+            }
+
+            /// Get the name of this event, without its value.  This is the
+            /// name used in the mapping database, except for numbered buttons
+            /// (which are all named `Number`).
+            pub fn name(self) -> &'static str {
+                use Event::*;
+                match self {
+                    Disconnect => "Disconnect",
+                    $($b(_) => stringify!($b),)*
+                    $($a(_) => stringify!($a),)*
+                    $($t(_) => stringify!($t),)*
+                    $($r(_) => stringify!($r),)*
+                    Pressure(button, _) => match button {
+                        $(Button::$p => concat!("Pressure:", stringify!($p)),)*
+                    },
+                    Number(..) => "Number",
+                    Raw { .. } => "Raw",
+                    Touch(..) => "Touch",
+                    TouchX(..) => "TouchX",
+                    TouchY(..) => "TouchY",
+                    Accel { .. } => "Accel",
+                    Gyro { .. } => "Gyro",
+                }
+            }
+
+            /// Get how the value is handled, or `None` for buttons and
+            /// events that aren't remapped.
+            pub(crate) fn analog(self) -> Option<Analog> {
+                use Event::*;
+                Some(match self {
+                    $($a(_) => Analog::Axis,)*
+                    $($t(_) | )* Pressure(..) => Analog::Trigger,
+                    $($r(_) => Analog::Relative,)*
+                    _ => return None,
+                })
+            }
+
+            /// Get the value, as `1.0` or `0.0` for buttons.
+            pub(crate) fn value(self) -> f64 {
+                use Event::*;
+                match self {
+                    $($b(p))|* | Number(_, p) | Touch(_, p) => {
+                        f64::from(u8::from(p))
+                    }
+                    $($a(v))|* | $($t(v))|* | $($r(v))|* => v,
+                    Pressure(_, v) | TouchX(_, v) | TouchY(_, v) => v,
+                    Raw { value, .. } => value.into(),
+                    Disconnect | Accel { .. } | Gyro { .. } => f64::NAN,
+                }
+            }
         }
+    };
+}
+
+codes! {
+    buttons {
+        0x01 Exit,
+        0x02 ActionA,
+        0x03 ActionB,
+        0x04 ActionC,
+        0x05 ActionH,
+        0x06 ActionV,
+        0x07 ActionD,
+        0x08 MenuL,
+        0x09 MenuR,
+        0x0A Joy,
+        0x0B Cam,
+        0x0C BumperL,
+        0x0D BumperR,
+        0x10 Up,
+        0x11 Down,
+        0x12 Left,
+        0x13 Right,
+        0x14 HatUp,
+        0x15 HatDown,
+        0x16 HatLeft,
+        0x17 HatRight,
+        0x18 MicUp,
+        0x19 MicDown,
+        0x1A MicLeft,
+        0x1B MicRight,
+        0x1C PovUp,
+        0x1D PovDown,
+        0x1E PovLeft,
+        0x1F PovRight,
+        0x2F MicPush,
+        0x30 Trigger,
+        0x31 Bumper,
+        0x32 ActionL,
+        0x33 ActionM,
+        0x34 ActionR,
+        0x35 Pinky,
+        0x36 PinkyForward,
+        0x37 PinkyBackward,
+        0x38 FlapsUp,
+        0x39 FlapsDown,
+        0x3A BoatForward,
+        0x3B BoatBackward,
+        0x3C AutopilotPath,
+        0x3D AutopilotAlt,
+        0x3E EngineMotorL,
+        0x3F EngineMotorR,
+        0x40 EngineFuelFlowL,
+        0x41 EngineFuelFlowR,
+        0x42 EngineIgnitionL,
+        0x43 EngineIgnitionR,
+        0x44 SpeedbrakeBackward,
+        0x45 SpeedbrakeForward,
+        0x46 ChinaBackward,
+        0x47 ChinaForward,
+        0x48 Apu,
+        0x49 RadarAltimeter,
+        0x4A LandingGearSilence,
+        0x4B Eac,
+        0x4C AutopilotToggle,
+        0x4D ThrottleButton,
+        0x50 Mouse,
+        0x51 PaddleLeft,
+        0x52 PaddleRight,
+        0x53 PinkyLeft,
+        0x54 PinkyRight,
+        0x55 Context,
+        0x56 Dpi,
+        0x59 Scroll,
+        0x5A TrimUp,
+        0x5B TrimDown,
+        0x5C TrimLeft,
+        0x5D TrimRight,
+        0x60 Touchpad,
+    }
+    axes {
+        0x20 JoyX,
+        0x21 JoyY,
+        0x22 JoyZ,
+        0x23 CamX,
+        0x24 CamY,
+        0x25 CamZ,
+        0x5E ActionWheelX,
+        0x5F ActionWheelY,
+    }
+    triggers {
+        0x0E TriggerL,
+        0x0F TriggerR,
+        0x26 Slew,
+        0x27 Throttle,
+        0x28 ThrottleL,
+        0x29 ThrottleR,
+        0x2A Volume,
+        0x2B Wheel,
+        0x2C Rudder,
+        0x2D Gas,
+        0x2E Brake,
+    }
+    relative {
+        0x4E MouseX,
+        0x4F MouseY,
+        0x57 ScrollX,
+        0x58 ScrollY,
+        0x6B MouseZ,
+        0x6C RotateX,
+        0x6D RotateY,
+        0x6E RotateZ,
+        0x6F Dial,
+        0x70 Knob,
+    }
+    pressure {
+        0x61 Up,
+        0x62 Down,
+        0x63 Left,
+        0x64 Right,
+        0x65 BumperL,
+        0x66 BumperR,
+        0x67 ActionA,
+        0x68 ActionB,
+        0x69 ActionH,
+        0x6A ActionV,
     }
+}
 
+impl Event {
+    /// Get this event, as the event with `code` (keeping the value), or
+    /// `None` if `code` is unassigned.
     #[inline(always)]
-    pub(crate) fn to_id(self) -> (u8, f64) {
-        use Event::*;
-        match self {
-            Disconnect => (0x00, f64::NAN),
-            Exit(p) => (0x01, f64::from(u8::from(p))),
-            ActionA(p) => (0x02, f64::from(u8::from(p))),
-            ActionB(p) => (0x03, f64::from(u8::from(p))),
-            ActionC(p) => (0x04, f64::from(u8::from(p))),
-            ActionH(p) => (0x05, f64::from(u8::from(p))),
-            ActionV(p) => (0x06, f64::from(u8::from(p))),
-            ActionD(p) => (0x07, f64::from(u8::from(p))),
-            MenuL(p) => (0x08, f64::from(u8::from(p))),
-            MenuR(p) => (0x09, f64::from(u8::from(p))),
-            Joy(p) => (0x0A, f64::from(u8::from(p))),
-            Cam(p) => (0x0B, f64::from(u8::from(p))),
-            BumperL(p) => (0x0C, f64::from(u8::from(p))),
-            BumperR(p) => (0x0D, f64::from(u8::from(p))),
-            TriggerL(t) => (0x0E, t),
-            TriggerR(t) => (0x0F, t),
-            Up(p) => (0x10, f64::from(u8::from(p))),
-            Down(p) => (0x11, f64::from(u8::from(p))),
-            Left(p) => (0x12, f64::from(u8::from(p))),
-            Right(p) => (0x13, f64::from(u8::from(p))),
-            HatUp(p) => (0x14, f64::from(u8::from(p))),
-            HatDown(p) => (0x15, f64::from(u8::from(p))),
-            HatLeft(p) => (0x16, f64::from(u8::from(p))),
-            HatRight(p) => (0x17, f64::from(u8::from(p))),
-            MicUp(p) => (0x18, f64::from(u8::from(p))),
-            MicDown(p) => (0x19, f64::from(u8::from(p))),
-            MicLeft(p) => (0x1A, f64::from(u8::from(p))),
-            MicRight(p) => (0x1B, f64::from(u8::from(p))),
-            PovUp(p) => (0x1C, f64::from(u8::from(p))),
-            PovDown(p) => (0x1D, f64::from(u8::from(p))),
-            PovLeft(p) => (0x1E, f64::from(u8::from(p))),
-            PovRight(p) => (0x1F, f64::from(u8::from(p))),
-            JoyX(v) => (0x20, v),
-            JoyY(v) => (0x21, v),
-            JoyZ(v) => (0x22, v),
-            CamX(v) => (0x23, v),
-            CamY(v) => (0x24, v),
-            CamZ(v) => (0x25, v),
-            Slew(t) => (0x26, t),
-            Throttle(t) => (0x27, t),
-            ThrottleL(t) => (0x28, t),
-            ThrottleR(t) => (0x29, t),
-            Volume(t) => (0x2A, t),
-            Wheel(t) => (0x2B, t),
-            Rudder(t) => (0x2C, t),
-            Gas(t) => (0x2D, t),
-            Brake(t) => (0x2E, t),
-            MicPush(p) => (0x2F, f64::from(u8::from(p))),
-            Trigger(p) => (0x30, f64::from(u8::from(p))),
-            Bumper(p) => (0x31, f64::from(u8::from(p))),
-            ActionL(p) => (0x32, f64::from(u8::from(p))),
-            ActionM(p) => (0x33, f64::from(u8::from(p))),
-            ActionR(p) => (0x34, f64::from(u8::from(p))),
-            Pinky(p) => (0x35, f64::from(u8::from(p))),
-            PinkyForward(p) => (0x36, f64::from(u8::from(p))),
-            PinkyBackward(p) => (0x37, f64::from(u8::from(p))),
-            FlapsUp(p) => (0x38, f64::from(u8::from(p))),
-            FlapsDown(p) => (0x39, f64::from(u8::from(p))),
-            BoatForward(p) => (0x3A, f64::from(u8::from(p))),
-            BoatBackward(p) => (0x3B, f64::from(u8::from(p))),
-            AutopilotPath(p) => (0x3C, f64::from(u8::from(p))),
-            AutopilotAlt(p) => (0x3D, f64::from(u8::from(p))),
-            EngineMotorL(p) => (0x3E, f64::from(u8::from(p))),
-            EngineMotorR(p) => (0x3F, f64::from(u8::from(p))),
-            EngineFuelFlowL(p) => (0x40, f64::from(u8::from(p))),
-            EngineFuelFlowR(p) => (0x41, f64::from(u8::from(p))),
-            EngineIgnitionL(p) => (0x42, f64::from(u8::from(p))),
-            EngineIgnitionR(p) => (0x43, f64::from(u8::from(p))),
-            SpeedbrakeBackward(p) => (0x44, f64::from(u8::from(p))),
-            SpeedbrakeForward(p) => (0x45, f64::from(u8::from(p))),
-            ChinaBackward(p) => (0x46, f64::from(u8::from(p))),
-            ChinaForward(p) => (0x47, f64::from(u8::from(p))),
-            Apu(p) => (0x48, f64::from(u8::from(p))),
-            RadarAltimeter(p) => (0x49, f64::from(u8::from(p))),
-            LandingGearSilence(p) => (0x4A, f64::from(u8::from(p))),
-            Eac(p) => (0x4B, f64::from(u8::from(p))),
-            AutopilotToggle(p) => (0x4C, f64::from(u8::from(p))),
-            ThrottleButton(p) => (0x4D, f64::from(u8::from(p))),
-            MouseX(v) => (0x4E, v),
-            MouseY(v) => (0x4F, v),
-            Mouse(p) => (0x50, f64::from(u8::from(p))),
-            Number(n, p) => (n as u8 | 0x80, f64::from(u8::from(p))),
-            PaddleLeft(p) => (0x51, f64::from(u8::from(p))),
-            PaddleRight(p) => (0x52, f64::from(u8::from(p))),
-            PinkyLeft(p) => (0x53, f64::from(u8::from(p))),
-            PinkyRight(p) => (0x54, f64::from(u8::from(p))),
-            Context(p) => (0x55, f64::from(u8::from(p))),
-            Dpi(p) => (0x56, f64::from(u8::from(p))),
-            ScrollX(v) => (0x57, v),
-            ScrollY(v) => (0x58, v),
-            Scroll(p) => (0x59, f64::from(u8::from(p))),
-            TrimUp(p) => (0x5A, f64::from(u8::from(p))),
-            TrimDown(p) => (0x5B, f64::from(u8::from(p))),
-            TrimLeft(p) => (0x5C, f64::from(u8::from(p))),
-            TrimRight(p) => (0x5D, f64::from(u8::from(p))),
-            ActionWheelX(v) => (0x5E, v),
-            ActionWheelY(v) => (0x5F, v),
-        }
+    pub(crate) fn remap(self, code: u8) -> Option<Self> {
+        Self::from_code(code, self.value())
+    }
+
+    /// Whether this event has an analog value rather than a pressed state.
+    pub fn is_axis(self) -> bool {
+        self.analog().is_some()
     }
 }
 
@@ -553,6 +687,25 @@ impl std::fmt::Display for Event {
             Pinky(p) => write!(f, "Pinky {}", pushed(p)),
             ActionWheelX(v) => write!(f, "ActionWheelX {}", v),
             ActionWheelY(v) => write!(f, "ActionWheelY {}", v),
+            Touchpad(p) => write!(f, "Touchpad {}", pushed(p)),
+            Touch(n, p) => {
+                let touch = if *p { "Down" } else { "Up" };
+                write!(f, "Touch({}) {}", n, touch)
+            }
+            TouchX(n, v) => write!(f, "TouchX({}) {}", n, v),
+            TouchY(n, v) => write!(f, "TouchY({}) {}", n, v),
+            Pressure(b, v) => write!(f, "Pressure({:?}) {}", b, v),
+            MouseZ(v) => write!(f, "MouseZ {}", v),
+            RotateX(v) => write!(f, "RotateX {}", v),
+            RotateY(v) => write!(f, "RotateY {}", v),
+            RotateZ(v) => write!(f, "RotateZ {}", v),
+            Dial(v) => write!(f, "Dial {}", v),
+            Knob(v) => write!(f, "Knob {}", v),
+            Accel { x, y, z } => write!(f, "Accel {} {} {}", x, y, z),
+            Gyro { x, y, z } => write!(f, "Gyro {} {} {}", x, y, z),
+            Raw { kind, code, value } => {
+                write!(f, "Raw({}, {}) {}", kind, code, value)
+            }
         }
     }
 }
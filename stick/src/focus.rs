@@ -1,9 +1,89 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Waker},
+};
+
+/// Focus state of every listener, for the process-wide functions.
+static LISTENERS: Mutex<Vec<Weak<Focus>>> = Mutex::new(Vec::new());
+
+/// Focus state shared between a [`Listener`](crate::Listener) and the
+/// controllers it returned.
+#[derive(Debug)]
+pub(crate) struct Focus {
+    focused: AtomicBool,
+    // Tasks to wake when focus changes.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            focused: AtomicBool::new(true),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Focus {
+    /// Create focus state for a new listener, affected by [`focus()`] and
+    /// [`unfocus()`].
+    pub(crate) fn new() -> Arc<Self> {
+        let focus = Arc::new(Self::default());
+        let mut listeners = LISTENERS.lock().unwrap();
+        listeners.retain(|f| f.strong_count() != 0);
+        listeners.push(Arc::downgrade(&focus));
+        focus
+    }
+
+    /// Change focus, waking any controllers so they can react.
+    pub(crate) fn set(&self, focused: bool) {
+        if self.focused.swap(focused, Ordering::Relaxed) != focused {
+            for waker in self.wakers.lock().unwrap().drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Check focus without registering for wake-ups.
+    pub(crate) fn get(&self) -> bool {
+        self.focused.load(Ordering::Relaxed)
+    }
+
+    /// Check focus, and get woken when it changes.
+    pub(crate) fn poll(&self, cx: &mut Context<'_>) -> bool {
+        let mut wakers = self.wakers.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        self.get()
+    }
+}
+
 /// Window grab focus, re-enable events if they were disabled.
+///
+/// Applies to every [`Listener`](crate::Listener); see
+/// [`Listener::focus()`](crate::Listener::focus) to only affect one.
 pub fn focus() {
     crate::raw::GLOBAL.with(|g| g.enable());
+    set_all(true);
 }
 
 /// Window ungrab focus, disable events.
+///
+/// Applies to every [`Listener`](crate::Listener); see
+/// [`Listener::unfocus()`](crate::Listener::unfocus) to only affect one.
 pub fn unfocus() {
     crate::raw::GLOBAL.with(|g| g.disable());
+    set_all(false);
+}
+
+fn set_all(focused: bool) {
+    let mut listeners = LISTENERS.lock().unwrap();
+    listeners.retain(|f| f.strong_count() != 0);
+    for focus in listeners.iter().filter_map(Weak::upgrade) {
+        focus.set(focused);
+    }
 }
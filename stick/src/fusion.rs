@@ -0,0 +1,357 @@
+use std::time::Instant;
+
+use crate::Event;
+
+/// Standard gravity (m/s²)
+const G: f64 = 9.80665;
+/// Gyroscope readings slower than this (rad/s) might mean the pad is still.
+const STILL_GYRO: f64 = 0.1;
+/// Accelerometer readings this far from 1 g might mean the pad is still.
+const STILL_ACCEL: f64 = 0.05 * G;
+/// Seconds the pad must be still before calibrating.
+const STILL_TIME: f64 = 0.5;
+/// Time constant (seconds) for gyroscope bias calibration.
+const BIAS_TIME: f64 = 1.0;
+/// Longest gap between gyroscope readings (seconds) that's integrated.
+const MAX_STEP: f64 = 0.1;
+
+/// Estimates a [`Controller`](crate::Controller)'s orientation from its
+/// [`Event::Accel`] and [`Event::Gyro`] events (Madgwick filter).
+///
+/// The gyroscope's bias (drift) is calibrated automatically whenever the
+/// controller is held still.
+///
+/// Readings are in the controller's frame as reported by the platform (`x` to
+/// the right, `y` up when held flat, and `z` towards the player).  The world's
+/// up is `z`; the orientation starts tilted to match the first accelerometer
+/// reading (and level, with the controller's `z` up, until there is one).
+///
+/// ```rust
+/// use stick::{Event, Fusion};
+///
+/// let mut fusion = Fusion::new();
+/// fusion.process(Event::Accel { x: 0.0, y: 0.0, z: 9.8 });
+/// fusion.process(Event::Gyro { x: 0.0, y: 0.0, z: 0.0 });
+/// let [w, x, y, z] = fusion.orientation();
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Fusion {
+    // Orientation quaternion (w, x, y, z), relative to the world.
+    quat: [f64; 4],
+    // Latest accelerometer reading.
+    accel: Option<[f64; 3]>,
+    // Estimated gyroscope bias.
+    bias: [f64; 3],
+    // How long the pad has been still (seconds).
+    still: f64,
+    // When the last gyroscope reading was.
+    time: Option<Instant>,
+    // Filter gain.
+    beta: f64,
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fusion {
+    /// Create a new orientation estimate, starting from the first
+    /// accelerometer reading.
+    pub fn new() -> Self {
+        Self {
+            quat: [1.0, 0.0, 0.0, 0.0],
+            accel: None,
+            bias: [0.0; 3],
+            still: 0.0,
+            time: None,
+            beta: 0.1,
+        }
+    }
+
+    /// Set how strongly the accelerometer corrects gyroscope drift (default
+    /// `0.1`).  Higher values correct faster, but are more affected by
+    /// shaking.
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Update with an event from the controller, received now.  Events that
+    /// aren't from motion sensors are ignored.
+    ///
+    /// Motion events often arrive several at a time, so prefer
+    /// [`process_at()`](Fusion::process_at) with
+    /// [`Controller::time()`](crate::Controller::time).
+    pub fn process(&mut self, event: Event) {
+        self.process_at(event, Instant::now());
+    }
+
+    /// Update with an event from the controller, read at `time`.
+    pub fn process_at(&mut self, event: Event, time: Instant) {
+        match event {
+            Event::Accel { x, y, z } => {
+                let first = self.accel.replace([x, y, z]).is_none();
+                if first {
+                    self.seed([x, y, z]);
+                }
+            }
+            Event::Gyro { x, y, z } => {
+                let dt = match self.time.replace(time) {
+                    Some(last) => time.saturating_duration_since(last),
+                    None => return,
+                };
+                let dt = dt.as_secs_f64();
+                if dt > 0.0 && dt <= MAX_STEP {
+                    self.calibrate([x, y, z], dt);
+                    let [bx, by, bz] = self.bias;
+                    self.update([x - bx, y - by, z - bz], dt);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the orientation quaternion (`[w, x, y, z]`), relative to the
+    /// world.  Yaw is relative to where the controller was pointing when
+    /// created or [reset](Fusion::reset()).
+    pub fn orientation(&self) -> [f64; 4] {
+        self.quat
+    }
+
+    /// Get gravity in the controller's frame (m/s²), as the accelerometer
+    /// reads it at rest (pointing up, away from the ground).
+    pub fn gravity(&self) -> [f64; 3] {
+        let [w, x, y, z] = self.quat;
+        [
+            2.0 * (x * z - w * y) * G,
+            2.0 * (w * x + y * z) * G,
+            (w * w - x * x - y * y + z * z) * G,
+        ]
+    }
+
+    /// Get the estimated gyroscope bias (rad/s), which is subtracted from
+    /// readings.
+    pub fn bias(&self) -> [f64; 3] {
+        self.bias
+    }
+
+    /// Reset the orientation (keeping the calibrated bias).
+    pub fn reset(&mut self) {
+        *self = Self {
+            bias: self.bias,
+            beta: self.beta,
+            ..Self::new()
+        };
+    }
+
+    // Start tilted so gravity points along the accelerometer reading, rather
+    // than waiting seconds for the filter to converge.
+    fn seed(&mut self, accel: [f64; 3]) {
+        let norm = length(accel);
+        if !(norm > 0.0 && norm.is_finite()) {
+            return;
+        }
+        let [ax, ay, az] = scale(accel, norm.recip());
+        // Shortest rotation from the reading to the world's up (`z`).
+        let quat = if az > -1.0 + 1e-9 {
+            [1.0 + az, ay, -ax, 0.0]
+        } else {
+            [0.0, 1.0, 0.0, 0.0]
+        };
+        let norm = quat.iter().map(|q| q * q).sum::<f64>().sqrt();
+        self.quat = quat.map(|q| q / norm);
+    }
+
+    // Calibrate the gyroscope bias while the pad is still.
+    fn calibrate(&mut self, gyro: [f64; 3], dt: f64) {
+        let speed = length(sub(gyro, self.bias));
+        let weight =
+            self.accel.map_or(f64::INFINITY, |a| (length(a) - G).abs());
+        if speed > STILL_GYRO || weight > STILL_ACCEL {
+            self.still = 0.0;
+            return;
+        }
+        self.still += dt;
+        if self.still >= STILL_TIME {
+            let rate = (dt / BIAS_TIME).min(1.0);
+            for (bias, gyro) in self.bias.iter_mut().zip(gyro) {
+                *bias += (gyro - *bias) * rate;
+            }
+        }
+    }
+
+    // Madgwick IMU update step.
+    fn update(&mut self, [gx, gy, gz]: [f64; 3], dt: f64) {
+        let [q0, q1, q2, q3] = self.quat;
+
+        // Rate of change of quaternion from gyroscope.
+        let mut dq = [
+            0.5 * (-q1 * gx - q2 * gy - q3 * gz),
+            0.5 * (q0 * gx + q2 * gz - q3 * gy),
+            0.5 * (q0 * gy - q1 * gz + q3 * gx),
+            0.5 * (q0 * gz + q1 * gy - q2 * gx),
+        ];
+
+        // Correct with the accelerometer (gradient descent).
+        if let Some(accel) = self.accel.filter(|a| length(*a) > 0.0) {
+            let [ax, ay, az] = scale(accel, length(accel).recip());
+            let s = [
+                4.0 * q0 * q2 * q2 + 2.0 * q2 * ax + 4.0 * q0 * q1 * q1
+                    - 2.0 * q1 * ay,
+                4.0 * q1 * q3 * q3 - 2.0 * q3 * ax + 4.0 * q0 * q0 * q1
+                    - 2.0 * q0 * ay
+                    - 4.0 * q1
+                    + 8.0 * q1 * q1 * q1
+                    + 8.0 * q1 * q2 * q2
+                    + 4.0 * q1 * az,
+                4.0 * q0 * q0 * q2 + 2.0 * q0 * ax + 4.0 * q2 * q3 * q3
+                    - 2.0 * q3 * ay
+                    - 4.0 * q2
+                    + 8.0 * q2 * q1 * q1
+                    + 8.0 * q2 * q2 * q2
+                    + 4.0 * q2 * az,
+                4.0 * q1 * q1 * q3 - 2.0 * q1 * ax + 4.0 * q2 * q2 * q3
+                    - 2.0 * q2 * ay,
+            ];
+            let norm = s.iter().map(|s| s * s).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                for (dq, s) in dq.iter_mut().zip(s) {
+                    *dq -= self.beta * s / norm;
+                }
+            }
+        }
+
+        // Integrate, and normalize.
+        let mut quat = self.quat;
+        for (q, dq) in quat.iter_mut().zip(dq) {
+            *q += dq * dt;
+        }
+        let norm = quat.iter().map(|q| q * q).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            self.quat = quat.map(|q| q / norm);
+        }
+    }
+}
+
+fn length([x, y, z]: [f64; 3]) -> f64 {
+    (x * x + y * y + z * z).sqrt()
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+    a.map(|a| a * s)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    // Feed gyroscope readings (after an accelerometer reading, if any) every
+    // 10 ms for `secs` seconds, returning the time after the last reading.
+    fn run(
+        fusion: &mut Fusion,
+        mut time: Instant,
+        accel: Option<[f64; 3]>,
+        [x, y, z]: [f64; 3],
+        secs: f64,
+    ) -> Instant {
+        for _ in 0..(secs * 100.0).round() as u32 {
+            if let Some([x, y, z]) = accel {
+                fusion.process_at(Event::Accel { x, y, z }, time);
+            }
+            fusion.process_at(Event::Gyro { x, y, z }, time);
+            time += Duration::from_millis(10);
+        }
+        time
+    }
+
+    fn close(a: &[f64], b: &[f64], tolerance: f64) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() <= tolerance)
+    }
+
+    #[test]
+    fn integrates_gyro() {
+        let mut fusion = Fusion::new();
+        // The first reading only starts the clock
+        let time =
+            run(&mut fusion, Instant::now(), None, [0.0, 0.0, 1.0], 0.01);
+        assert_eq!(fusion.orientation(), [1.0, 0.0, 0.0, 0.0]);
+        // 1 rad/s around z for 1 second
+        run(&mut fusion, time, None, [0.0, 0.0, 1.0], 1.0);
+        let half = 0.5f64;
+        let expected = [half.cos(), 0.0, 0.0, half.sin()];
+        assert!(close(&fusion.orientation(), &expected, 1e-3));
+    }
+
+    #[test]
+    fn skips_gaps() {
+        let mut fusion = Fusion::new();
+        let time = Instant::now();
+        let gyro = Event::Gyro {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        fusion.process_at(gyro, time);
+        // Too long since the last reading
+        fusion.process_at(gyro, time + Duration::from_secs(1));
+        assert_eq!(fusion.orientation(), [1.0, 0.0, 0.0, 0.0]);
+        // Out of order, and at the same time
+        fusion.process_at(gyro, time);
+        fusion.process_at(gyro, time);
+        assert_eq!(fusion.orientation(), [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn calibrates_bias_when_still() {
+        let mut fusion = Fusion::new();
+        let up = Some([0.0, 0.0, G]);
+        let drift = [0.05, -0.02, 0.0];
+        let time = run(&mut fusion, Instant::now(), up, drift, 5.0);
+        assert!(close(&fusion.bias(), &drift, 0.005), "{:?}", fusion.bias());
+
+        // Moving doesn't change the calibration
+        let bias = fusion.bias();
+        run(&mut fusion, time, up, [1.0, 0.0, 0.0], 1.0);
+        assert_eq!(fusion.bias(), bias);
+
+        // Resetting keeps the calibration
+        fusion.reset();
+        assert_eq!(fusion.bias(), bias);
+        assert_eq!(fusion.orientation(), [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn starts_from_accelerometer() {
+        for accel in [[0.0, G, 0.0], [0.0, 0.0, -G], [3.0, -4.0, 8.0]] {
+            let mut fusion = Fusion::new();
+            assert!(close(&fusion.gravity(), &[0.0, 0.0, G], 1e-9));
+            let [x, y, z] = accel;
+            fusion.process_at(Event::Accel { x, y, z }, Instant::now());
+            let expected = scale(accel, G / length(accel));
+            assert!(close(&fusion.gravity(), &expected, 1e-9), "{:?}", accel);
+        }
+    }
+
+    #[test]
+    fn accelerometer_corrects_tilt() {
+        let mut fusion = Fusion::new().beta(0.5);
+        let time = Instant::now();
+        let time = run(&mut fusion, time, Some([0.0, 0.0, G]), [0.0; 3], 0.1);
+        // Turned on its side
+        let side = [0.0, G, 0.0];
+        run(&mut fusion, time, Some(side), [0.0; 3], 10.0);
+        assert!(
+            close(&fusion.gravity(), &side, 0.1),
+            "{:?}",
+            fusion.gravity()
+        );
+    }
+}
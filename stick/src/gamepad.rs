@@ -1,7 +1,20 @@
-// FIXME: Use in crate.
+//! A typed layer over [`Controller`] for gamepads, with a fixed standard
+//! layout.
 
-/// An event from a `Gamepad`.
-enum GamepadEvent {
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Controller, Event, Kind};
+
+/// An event from a [`Gamepad`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum GamepadEvent {
+    /// Gamepad unplugged.
+    Disconnect,
     /// Main / Home / Mode / XBox Button / PS3 Button
     Home(bool),
     /// Select / Back / Minus / Menu Button
@@ -16,13 +29,14 @@ enum GamepadEvent {
     Left(bool),
     /// D-Pad Right
     Right(bool),
-    /// The primary face action button (Circle, A, 1)
+    /// The primary face action button, on the right (Circle, A, 1)
     A(bool),
-    /// The secondary face action button (Cross, B, 2)
+    /// The secondary face action button, on the bottom (Cross, B, 2)
     B(bool),
     /// The topmost face action button (Triangle, may be either X or Y, 3 or 4)
     Top(bool),
-    /// The remaining face action button (Square, may be either X or Y, 3 or 4)
+    /// The remaining face action button, on the left (Square, may be either X
+    /// or Y, 3 or 4)
     Use(bool),
     /// Left bumper button
     BumperL(bool),
@@ -33,17 +47,17 @@ enum GamepadEvent {
     /// The direction joystick push button
     Dir(bool),
     /// Camera joystick X
-    CamX(f32),
+    CamX(f64),
     /// Camera joystick Y
-    CamY(f32),
+    CamY(f64),
     /// Direction joystick X
-    DirX(f32),
+    DirX(f64),
     /// Direction joystick Y
-    DirY(f32),
+    DirY(f64),
     /// Left trigger
-    TriggerL(f32),
+    TriggerL(f64),
     /// Right trigger
-    TriggerR(f32),
+    TriggerR(f64),
     /// Extended Gamepad: Top grip button on the left
     PaddleL(bool),
     /// Extended Gamepad: Top grip button on the right
@@ -53,3 +67,421 @@ enum GamepadEvent {
     /// Extended Gamepad: Lower grip button on the right
     GripR(bool),
 }
+
+impl GamepadEvent {
+    /// Convert a controller event to its place in the gamepad layout, `None`
+    /// if it isn't part of it.
+    pub fn from_event(event: Event) -> Option<Self> {
+        use GamepadEvent as G;
+
+        Some(match event {
+            Event::Disconnect => G::Disconnect,
+            Event::Exit(p) => G::Home(p),
+            Event::MenuL(p) => G::Menu(p),
+            Event::MenuR(p) => G::Play(p),
+            Event::Up(p) => G::Up(p),
+            Event::Down(p) => G::Down(p),
+            Event::Left(p) => G::Left(p),
+            Event::Right(p) => G::Right(p),
+            Event::ActionA(p) => G::A(p),
+            Event::ActionB(p) => G::B(p),
+            Event::ActionV(p) => G::Top(p),
+            Event::ActionH(p) => G::Use(p),
+            Event::BumperL(p) => G::BumperL(p),
+            Event::BumperR(p) => G::BumperR(p),
+            Event::Cam(p) => G::Cam(p),
+            Event::Joy(p) => G::Dir(p),
+            Event::CamX(v) => G::CamX(v),
+            Event::CamY(v) => G::CamY(v),
+            Event::JoyX(v) => G::DirX(v),
+            Event::JoyY(v) => G::DirY(v),
+            Event::TriggerL(v) => G::TriggerL(v),
+            Event::TriggerR(v) => G::TriggerR(v),
+            Event::PaddleLeft(p) => G::PaddleL(p),
+            Event::PaddleRight(p) => G::PaddleR(p),
+            Event::PinkyLeft(p) => G::GripL(p),
+            Event::PinkyRight(p) => G::GripR(p),
+            _ => return None,
+        })
+    }
+
+    /// The button this event is for, and whether it's pressed (`None` for
+    /// axes and disconnects).
+    pub fn button(self) -> Option<(GamepadButton, bool)> {
+        use GamepadButton as B;
+
+        Some(match self {
+            Self::Home(p) => (B::Home, p),
+            Self::Menu(p) => (B::Menu, p),
+            Self::Play(p) => (B::Play, p),
+            Self::Up(p) => (B::Up, p),
+            Self::Down(p) => (B::Down, p),
+            Self::Left(p) => (B::Left, p),
+            Self::Right(p) => (B::Right, p),
+            Self::A(p) => (B::A, p),
+            Self::B(p) => (B::B, p),
+            Self::Top(p) => (B::Top, p),
+            Self::Use(p) => (B::Use, p),
+            Self::BumperL(p) => (B::BumperL, p),
+            Self::BumperR(p) => (B::BumperR, p),
+            Self::Cam(p) => (B::Cam, p),
+            Self::Dir(p) => (B::Dir, p),
+            Self::PaddleL(p) => (B::PaddleL, p),
+            Self::PaddleR(p) => (B::PaddleR, p),
+            Self::GripL(p) => (B::GripL, p),
+            Self::GripR(p) => (B::GripR, p),
+            _ => return None,
+        })
+    }
+}
+
+/// A button in the gamepad layout (see [`GamepadEvent`] for where each one
+/// is).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GamepadButton {
+    /// Main / Home / Mode
+    Home,
+    /// Select / Back / Minus / Menu
+    Menu,
+    /// Start / Forward / Plus / Play
+    Play,
+    /// D-Pad Up
+    Up,
+    /// D-Pad Down
+    Down,
+    /// D-Pad Left
+    Left,
+    /// D-Pad Right
+    Right,
+    /// Right face action button
+    A,
+    /// Bottom face action button
+    B,
+    /// Top face action button
+    Top,
+    /// Left face action button
+    Use,
+    /// Left bumper
+    BumperL,
+    /// Right bumper
+    BumperR,
+    /// Camera joystick push
+    Cam,
+    /// Direction joystick push
+    Dir,
+    /// Top grip button on the left
+    PaddleL,
+    /// Top grip button on the right
+    PaddleR,
+    /// Lower grip button on the left
+    GripL,
+    /// Lower grip button on the right
+    GripR,
+}
+
+impl GamepadButton {
+    /// The label printed on this button for a kind of gamepad, for showing
+    /// players which button to press.
+    pub fn label(self, kind: Kind) -> &'static str {
+        use GamepadButton as B;
+
+        match (kind, self) {
+            (Kind::PlayStation, B::Home) => "PS",
+            (Kind::PlayStation, B::Menu) => "Select",
+            (Kind::PlayStation, B::A) => "Circle",
+            (Kind::PlayStation, B::B) => "Cross",
+            (Kind::PlayStation, B::Top) => "Triangle",
+            (Kind::PlayStation, B::Use) => "Square",
+            (Kind::PlayStation, B::BumperL) => "L1",
+            (Kind::PlayStation, B::BumperR) => "R1",
+            (Kind::PlayStation, B::Dir) => "L3",
+            (Kind::PlayStation, B::Cam) => "R3",
+            (Kind::Nintendo, B::Menu) => "-",
+            (Kind::Nintendo, B::Play) => "+",
+            (Kind::Nintendo, B::A) => "A",
+            (Kind::Nintendo, B::B) => "B",
+            (Kind::Nintendo, B::Top) => "X",
+            (Kind::Nintendo, B::Use) => "Y",
+            (Kind::Nintendo, B::BumperL) => "L",
+            (Kind::Nintendo, B::BumperR) => "R",
+            (_, B::Home) => "Home",
+            (_, B::Menu) => "Back",
+            (_, B::Play) => "Start",
+            (_, B::Up) => "Up",
+            (_, B::Down) => "Down",
+            (_, B::Left) => "Left",
+            (_, B::Right) => "Right",
+            (_, B::A) => "B",
+            (_, B::B) => "A",
+            (_, B::Top) => "Y",
+            (_, B::Use) => "X",
+            (_, B::BumperL) => "LB",
+            (_, B::BumperR) => "RB",
+            (_, B::Dir) => "LS",
+            (_, B::Cam) => "RS",
+            (_, B::PaddleL) => "Left Paddle",
+            (_, B::PaddleR) => "Right Paddle",
+            (_, B::GripL) => "Left Grip",
+            (_, B::GripR) => "Right Grip",
+        }
+    }
+}
+
+/// The state of every button and axis of a [`Gamepad`].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GamepadState {
+    /// Which buttons are held, a bit for each [`GamepadButton`].
+    buttons: u32,
+    /// Camera joystick X
+    pub cam_x: f64,
+    /// Camera joystick Y
+    pub cam_y: f64,
+    /// Direction joystick X
+    pub dir_x: f64,
+    /// Direction joystick Y
+    pub dir_y: f64,
+    /// Left trigger
+    pub trigger_l: f64,
+    /// Right trigger
+    pub trigger_r: f64,
+}
+
+impl GamepadState {
+    /// Whether a button is held.
+    pub fn pressed(&self, button: GamepadButton) -> bool {
+        self.buttons & (1 << button as u32) != 0
+    }
+
+    // Update the state for an event.
+    fn update(&mut self, event: GamepadEvent) {
+        if let Some((button, pressed)) = event.button() {
+            let bit = 1 << button as u32;
+            if pressed {
+                self.buttons |= bit;
+            } else {
+                self.buttons &= !bit;
+            }
+            return;
+        }
+        match event {
+            GamepadEvent::Disconnect => *self = Self::default(),
+            GamepadEvent::CamX(v) => self.cam_x = v,
+            GamepadEvent::CamY(v) => self.cam_y = v,
+            GamepadEvent::DirX(v) => self.dir_x = v,
+            GamepadEvent::DirY(v) => self.dir_y = v,
+            GamepadEvent::TriggerL(v) => self.trigger_l = v,
+            GamepadEvent::TriggerR(v) => self.trigger_r = v,
+            _ => {}
+        }
+    }
+}
+
+/// A [`Controller`] as a gamepad with a fixed standard layout: face buttons,
+/// d-pad, two joysticks, triggers, bumpers, paddles and grips.
+///
+/// Controllers of a gamepad [`Kind`] (`Xbox`, `PlayStation`, `Nintendo` and
+/// `Gamepad`) are mapped to this layout by the mapping database, so every
+/// input lands in the same place whichever gamepad it came from.  Other
+/// controllers work too, but only the inputs they share with the layout are
+/// reported.  Events outside the layout are dropped; use the [`Controller`]
+/// directly (through [`controller_mut()`](Gamepad::controller_mut)) for
+/// them.
+///
+/// ```rust,no_run
+/// # async fn run(controller: stick::Controller) {
+/// use stick::{Gamepad, GamepadButton, GamepadEvent};
+///
+/// let mut gamepad = Gamepad::new(controller);
+/// println!("Press {} to jump", gamepad.label(GamepadButton::B));
+/// loop {
+///     match (&mut gamepad).await {
+///         GamepadEvent::Disconnect => break,
+///         GamepadEvent::B(true) => println!("Jump!"),
+///         _ => {}
+///     }
+///     println!("Moving {}", gamepad.state().dir_x);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Gamepad {
+    controller: Controller,
+    state: GamepadState,
+}
+
+impl Gamepad {
+    /// Use a controller as a gamepad.
+    pub fn new(controller: Controller) -> Self {
+        Self {
+            controller,
+            state: GamepadState::default(),
+        }
+    }
+
+    /// Whether the mapping database says this is a gamepad, so the layout
+    /// is guaranteed.
+    pub fn is_gamepad(&self) -> bool {
+        matches!(
+            self.controller.kind(),
+            Kind::Xbox | Kind::PlayStation | Kind::Nintendo | Kind::Gamepad
+        )
+    }
+
+    /// The state of every button and axis, as of the last event.
+    pub fn state(&self) -> &GamepadState {
+        &self.state
+    }
+
+    /// The label printed on a button of this gamepad.
+    pub fn label(&self, button: GamepadButton) -> &'static str {
+        button.label(self.controller.kind())
+    }
+
+    /// Get the underlying controller.
+    pub fn controller(&self) -> &Controller {
+        &self.controller
+    }
+
+    /// Get the underlying controller mutably (for rumble, or polling
+    /// [`Event`]s outside the layout).
+    pub fn controller_mut(&mut self) -> &mut Controller {
+        &mut self.controller
+    }
+
+    /// Stop using the controller as a gamepad.
+    pub fn into_controller(self) -> Controller {
+        self.controller
+    }
+}
+
+impl Future for Gamepad {
+    type Output = GamepadEvent;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<GamepadEvent> {
+        let this = self.get_mut();
+
+        while let Poll::Ready(event) = Pin::new(&mut this.controller).poll(cx) {
+            if let Some(event) = GamepadEvent::from_event(event) {
+                this.state.update(event);
+                return Poll::Ready(event);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures::stream::Stream for Gamepad {
+    type Item = GamepadEvent;
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<GamepadEvent>> {
+        match self.poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(e) => match e {
+                GamepadEvent::Disconnect => Poll::Ready(None),
+                e => Poll::Ready(Some(e)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Remap;
+
+    // Connect a mock PlayStation-style controller as a gamepad.
+    fn gamepad() -> (crate::raw::Mock, Gamepad) {
+        let raw = crate::raw::Mock {
+            id: 0x0300_ABCD_1234_0100,
+            ..Default::default()
+        };
+        let remap = Remap::new().load("0300ABCD12340100Pad\tp").unwrap();
+        let controller = Controller::new(Box::new(raw.clone()), &remap);
+        (raw, Gamepad::new(controller))
+    }
+
+    // Poll a gamepad until it has no events ready.
+    fn events(gamepad: &mut Gamepad) -> Vec<GamepadEvent> {
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+        let mut events = Vec::new();
+        while let Poll::Ready(event) = Pin::new(&mut *gamepad).poll(&mut cx) {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn from_event() {
+        use GamepadEvent as G;
+
+        let event = GamepadEvent::from_event;
+        assert_eq!(event(Event::ActionV(true)), Some(G::Top(true)));
+        assert_eq!(event(Event::ActionH(false)), Some(G::Use(false)));
+        assert_eq!(event(Event::Joy(true)), Some(G::Dir(true)));
+        assert_eq!(event(Event::JoyY(-0.5)), Some(G::DirY(-0.5)));
+        assert_eq!(event(Event::PinkyLeft(true)), Some(G::GripL(true)));
+        assert_eq!(event(Event::Disconnect), Some(G::Disconnect));
+        // Outside the layout
+        assert_eq!(event(Event::ActionC(true)), None);
+        assert_eq!(event(Event::Number(1, true)), None);
+        assert_eq!(event(Event::Throttle(0.5)), None);
+
+        assert_eq!(G::Play(true).button(), Some((GamepadButton::Play, true)));
+        assert_eq!(G::TriggerL(1.0).button(), None);
+    }
+
+    #[test]
+    fn state() {
+        let (raw, mut gamepad) = gamepad();
+        assert!(gamepad.is_gamepad());
+        raw.send([
+            Event::ActionA(true),
+            Event::PinkyRight(true),
+            Event::Number(1, true),
+            Event::ActionA(false),
+            Event::JoyX(0.5),
+            Event::CamY(-1.0),
+            Event::TriggerR(0.25),
+        ]);
+        // Events outside the layout are dropped
+        assert_eq!(events(&mut gamepad).len(), 6);
+        let state = *gamepad.state();
+        assert!(!state.pressed(GamepadButton::A));
+        assert!(state.pressed(GamepadButton::GripR));
+        assert!(!state.pressed(GamepadButton::GripL));
+        assert_eq!((state.dir_x, state.cam_y), (0.5, -1.0));
+        assert_eq!((state.trigger_l, state.trigger_r), (0.0, 0.25));
+
+        // Disconnecting resets everything
+        raw.send([Event::Disconnect]);
+        assert_eq!(events(&mut gamepad), [GamepadEvent::Disconnect]);
+        assert_eq!(*gamepad.state(), GamepadState::default());
+    }
+
+    #[test]
+    fn labels() {
+        use GamepadButton as B;
+
+        let labels = |kind| {
+            [B::A, B::B, B::Top, B::Menu, B::Home, B::Dir]
+                .map(|button| button.label(kind))
+        };
+        let xbox = ["B", "A", "Y", "Back", "Home", "LS"];
+        assert_eq!(labels(Kind::Xbox), xbox);
+        assert_eq!(labels(Kind::Gamepad), xbox);
+        assert_eq!(labels(Kind::Flight), xbox);
+        assert_eq!(labels(Kind::Unknown), xbox);
+        assert_eq!(
+            labels(Kind::PlayStation),
+            ["Circle", "Cross", "Triangle", "Select", "PS", "L3"],
+        );
+        assert_eq!(labels(Kind::Nintendo), ["A", "B", "X", "-", "Home", "LS"]);
+
+        let (_, gamepad) = gamepad();
+        assert_eq!(gamepad.label(B::Use), "Square");
+    }
+}
@@ -0,0 +1,264 @@
+use crate::Event;
+
+/// A touchpad gesture, recognized by [`Gestures`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Gesture {
+    /// One finger swiped up
+    SwipeUp,
+    /// One finger swiped down
+    SwipeDown,
+    /// One finger swiped left
+    SwipeLeft,
+    /// One finger swiped right
+    SwipeRight,
+    /// Touchpad clicked with two (or more) fingers on it
+    TwoFingerClick,
+}
+
+// A finger on the touchpad.
+#[derive(Debug, Copy, Clone)]
+struct Contact {
+    n: u8,
+    // Where the finger started (NaN until known).
+    start: (f64, f64),
+    // Where the finger is now.
+    now: (f64, f64),
+}
+
+/// Recognizes touchpad [`Gesture`]s from a [`Controller`](crate::Controller)'s
+/// touch events.
+///
+/// ```rust
+/// use stick::{Event, Gesture, Gestures};
+///
+/// let mut gestures = Gestures::new();
+/// for event in [
+///     Event::Touch(0, true),
+///     Event::TouchX(0, 0.1),
+///     Event::TouchX(0, 0.9),
+///     Event::Touch(0, false),
+/// ] {
+///     if let Some(gesture) = gestures.process(event) {
+///         assert_eq!(gesture, Gesture::SwipeRight);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Gestures {
+    // Fingers currently on the touchpad.
+    contacts: Vec<Contact>,
+    // Most fingers on the touchpad at once since they were all lifted.
+    fingers: usize,
+    // Whether a click already ended the current gesture.
+    clicked: bool,
+    // Minimum distance for a swipe.
+    distance: f64,
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gestures {
+    /// Create a new gesture recognizer.
+    pub fn new() -> Self {
+        Self {
+            contacts: Vec::new(),
+            fingers: 0,
+            clicked: false,
+            distance: 0.25,
+        }
+    }
+
+    /// Set the minimum distance a finger must travel to swipe, as a fraction
+    /// of the touchpad's size (default `0.25`).
+    pub fn distance(mut self, distance: f64) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Update with an event from the controller, returning a gesture if one
+    /// was completed.  Events that aren't from the touchpad are ignored.
+    pub fn process(&mut self, event: Event) -> Option<Gesture> {
+        match event {
+            Event::Touch(n, true) => {
+                if !self.contacts.iter().any(|c| c.n == n) {
+                    self.contacts.push(Contact {
+                        n,
+                        start: (f64::NAN, f64::NAN),
+                        now: (f64::NAN, f64::NAN),
+                    });
+                }
+                self.fingers = self.fingers.max(self.contacts.len());
+                None
+            }
+            Event::Touch(n, false) => {
+                let i = self.contacts.iter().position(|c| c.n == n)?;
+                let contact = self.contacts.remove(i);
+                if !self.contacts.is_empty() {
+                    return None;
+                }
+                let single = self.fingers == 1 && !self.clicked;
+                self.fingers = 0;
+                self.clicked = false;
+                if single {
+                    self.swipe(contact)
+                } else {
+                    None
+                }
+            }
+            Event::TouchX(n, x) => {
+                let contact = self.contacts.iter_mut().find(|c| c.n == n)?;
+                if contact.start.0.is_nan() {
+                    contact.start.0 = x;
+                }
+                contact.now.0 = x;
+                None
+            }
+            Event::TouchY(n, y) => {
+                let contact = self.contacts.iter_mut().find(|c| c.n == n)?;
+                if contact.start.1.is_nan() {
+                    contact.start.1 = y;
+                }
+                contact.now.1 = y;
+                None
+            }
+            Event::Touchpad(true) => {
+                self.clicked = !self.contacts.is_empty();
+                if self.contacts.len() >= 2 {
+                    Some(Gesture::TwoFingerClick)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Check if a lifted finger travelled far enough to be a swipe.
+    fn swipe(&self, contact: Contact) -> Option<Gesture> {
+        // Axes that never moved (or were never reported) count as zero.
+        let x = contact.now.0 - contact.start.0;
+        let y = contact.now.1 - contact.start.1;
+        let x = if x.is_nan() { 0.0 } else { x };
+        let y = if y.is_nan() { 0.0 } else { y };
+        if x.abs().max(y.abs()) < self.distance {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 {
+                Gesture::SwipeRight
+            } else {
+                Gesture::SwipeLeft
+            })
+        } else {
+            Some(if y > 0.0 {
+                Gesture::SwipeDown
+            } else {
+                Gesture::SwipeUp
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Process events, returning the gestures recognized.
+    fn gestures(gestures: &mut Gestures, events: &[Event]) -> Vec<Gesture> {
+        events.iter().filter_map(|e| gestures.process(*e)).collect()
+    }
+
+    // A one finger swipe from `from` to `to`.
+    fn swipe(from: (f64, f64), to: (f64, f64)) -> [Event; 6] {
+        [
+            Event::Touch(0, true),
+            Event::TouchX(0, from.0),
+            Event::TouchY(0, from.1),
+            Event::TouchX(0, to.0),
+            Event::TouchY(0, to.1),
+            Event::Touch(0, false),
+        ]
+    }
+
+    #[test]
+    fn swipes() {
+        let mut g = Gestures::new();
+        let center = (0.5, 0.5);
+        for (to, gesture) in [
+            ((0.5, 0.1), Gesture::SwipeUp),
+            ((0.5, 0.9), Gesture::SwipeDown),
+            ((0.1, 0.6), Gesture::SwipeLeft),
+            ((0.9, 0.4), Gesture::SwipeRight),
+        ] {
+            assert_eq!(gestures(&mut g, &swipe(center, to)), [gesture]);
+        }
+        // Too short
+        assert!(gestures(&mut g, &swipe(center, (0.6, 0.6))).is_empty());
+        let mut g = Gestures::new().distance(0.05);
+        let short = gestures(&mut g, &swipe(center, (0.6, 0.5)));
+        assert_eq!(short, [Gesture::SwipeRight]);
+    }
+
+    #[test]
+    fn one_axis_reported() {
+        let mut g = Gestures::new();
+        let events = [
+            Event::Touch(0, true),
+            Event::TouchY(0, 0.9),
+            Event::TouchY(0, 0.2),
+            Event::Touch(0, false),
+        ];
+        assert_eq!(gestures(&mut g, &events), [Gesture::SwipeUp]);
+    }
+
+    #[test]
+    fn two_finger_click() {
+        let mut g = Gestures::new();
+        let events = [
+            Event::Touch(0, true),
+            Event::TouchX(0, 0.1),
+            Event::Touch(1, true),
+            Event::TouchX(1, 0.2),
+            Event::Touchpad(true),
+            Event::Touchpad(false),
+            Event::TouchX(0, 0.9),
+            Event::Touch(1, false),
+            Event::Touch(0, false),
+        ];
+        // Lifting the fingers one at a time isn't a swipe
+        assert_eq!(gestures(&mut g, &events), [Gesture::TwoFingerClick]);
+    }
+
+    #[test]
+    fn click_cancels_swipe() {
+        let mut g = Gestures::new();
+        let events = [
+            Event::Touch(0, true),
+            Event::TouchX(0, 0.1),
+            Event::Touchpad(true),
+            Event::Touchpad(false),
+            Event::TouchX(0, 0.9),
+            Event::Touch(0, false),
+        ];
+        assert!(gestures(&mut g, &events).is_empty());
+        // The next swipe isn't affected
+        let next = gestures(&mut g, &swipe((0.1, 0.5), (0.9, 0.5)));
+        assert_eq!(next, [Gesture::SwipeRight]);
+    }
+
+    #[test]
+    fn ignores_other_events() {
+        let mut g = Gestures::new();
+        let events = [
+            Event::Touch(0, false),
+            Event::TouchX(1, 0.5),
+            Event::ActionA(true),
+            Event::Touchpad(true),
+        ];
+        assert!(gestures(&mut g, &events).is_empty());
+    }
+}
@@ -110,13 +110,32 @@
 #[macro_use]
 extern crate log;
 
+mod aim;
+mod auto;
+mod caps;
 mod ctlr;
+mod diag;
+mod encoder;
+mod error;
 mod event;
 mod focus;
+mod fusion;
+mod gamepad;
+mod gesture;
 mod listener;
+pub mod output;
 mod raw;
+mod sdb;
 
-pub use ctlr::{Controller, Remap};
-pub use event::Event;
+pub use aim::{Aim, AimOutput, AimSpace};
+pub use caps::{Axis, Capabilities, Effect};
+pub use ctlr::{Controller, Kind, Remap};
+pub use diag::{set_diagnostics, silence_diagnostics, Diagnostic};
+pub use encoder::Encoder;
+pub use error::Error;
+pub use event::{Button, Event};
 pub use focus::{focus, unfocus};
+pub use fusion::Fusion;
+pub use gamepad::{Gamepad, GamepadButton, GamepadEvent, GamepadState};
+pub use gesture::{Gesture, Gestures};
 pub use listener::Listener;
@@ -2,13 +2,14 @@ use std::{
     fmt::Debug,
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
-use crate::Remap;
+use crate::{focus::Focus, Error, Remap};
 
 /// Listener for when new controllers are plugged in.
-pub struct Listener(Box<dyn crate::raw::Listener>);
+pub struct Listener(Box<dyn crate::raw::Listener>, Arc<Focus>);
 
 impl Debug for Listener {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -24,8 +25,39 @@ impl Default for Listener {
 
 impl Listener {
     /// Create a new listener for when new controllers are plugged in.
+    ///
+    /// If listening isn't possible (for example, in a sandbox without access
+    /// to input devices), the listener never finds any controllers.  Use
+    /// [`Listener::try_new()`] to handle the error instead.
     pub fn new(remap: Remap) -> Self {
-        Self(crate::raw::GLOBAL.with(|g| g.listener(remap)))
+        Self::try_new(remap).unwrap_or_else(|_| {
+            Self(Box::new(crate::raw::FakeListener), Focus::new())
+        })
+    }
+
+    /// Try to create a new listener for when new controllers are plugged in.
+    pub fn try_new(remap: Remap) -> Result<Self, Error> {
+        let listener = crate::raw::GLOBAL.with(|g| g.listener(remap))?;
+        Ok(Self(listener, Focus::new()))
+    }
+
+    /// Take errors from controllers that were plugged in, but couldn't be set
+    /// up (these controllers are skipped).
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        self.0.take_errors()
+    }
+
+    /// Window grab focus, re-enable events for controllers from this listener
+    /// and resynchronize them with the current state of the hardware.
+    pub fn focus(&self) {
+        self.1.set(true);
+    }
+
+    /// Window ungrab focus, disable events for controllers from this listener.
+    ///
+    /// Any pressed buttons are released, and axes are returned to zero.
+    pub fn unfocus(&self) {
+        self.1.set(false);
     }
 }
 
@@ -33,7 +65,11 @@ impl Future for Listener {
     type Output = crate::Controller;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.get_mut().0).poll(cx)
+        let this = self.get_mut();
+        Pin::new(&mut this.0).poll(cx).map(|mut controller| {
+            controller.set_focus(this.1.clone());
+            controller
+        })
     }
 }
 #[cfg(feature = "stream")]
@@ -49,3 +85,40 @@ impl futures::stream::Stream for Listener {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctlr::tests::events, raw::Mock, Controller, Event};
+
+    // A listener, and a controller from it.
+    fn listener() -> (Listener, Mock, Controller) {
+        let listener =
+            Listener(Box::new(crate::raw::FakeListener), Focus::new());
+        let raw = Mock::default();
+        let mut controller =
+            Controller::new(Box::new(raw.clone()), &Remap::new());
+        controller.set_focus(listener.1.clone());
+        (listener, raw, controller)
+    }
+
+    #[test]
+    fn focus_per_listener() {
+        let (a, raw_a, mut controller_a) = listener();
+        let (_b, raw_b, mut controller_b) = listener();
+        raw_a.send([Event::ActionA(true)]);
+        raw_b.send([Event::ActionA(true)]);
+        assert_eq!(events(&mut controller_a), ["ActionA(true)"]);
+        assert_eq!(events(&mut controller_b), ["ActionA(true)"]);
+
+        a.unfocus();
+        raw_a.send([Event::ActionB(true)]);
+        raw_b.send([Event::ActionB(true)]);
+        assert_eq!(events(&mut controller_a), ["ActionA(false)"]);
+        assert_eq!(events(&mut controller_b), ["ActionB(true)"]);
+
+        a.focus();
+        raw_a.send([Event::ActionH(true)]);
+        assert_eq!(events(&mut controller_a), ["ActionH(true)"]);
+    }
+}
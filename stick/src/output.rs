@@ -0,0 +1,156 @@
+//! Virtual controllers, which other programs see as real hardware.
+//!
+//! Currently only supported on Linux (through `/dev/uinput`).
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Error, Event};
+
+/// Which inputs a [`VirtualController`] has.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Layout {
+    /// Xbox 360 controller (identifies as one, so games recognize it), with
+    /// the d-pad on a hat like the `xpad` driver's (from either
+    /// [`Event::Up`] and friends, or [`Event::PovUp`] and friends)
+    Xbox,
+    /// Generic joystick, with a stick, throttle, rudder, hat and 16 buttons
+    Joystick,
+    /// Custom set of inputs, listed as events (the values are ignored).
+    /// Creating the virtual controller fails if the list is empty, or has an
+    /// event the platform can't send.
+    Custom(Vec<Event>),
+}
+
+impl Layout {
+    /// Get the events the virtual controller can send.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn events(&self) -> Vec<Event> {
+        use Event::*;
+        match self {
+            Layout::Xbox => vec![
+                ActionA(false),
+                ActionB(false),
+                ActionH(false),
+                ActionV(false),
+                BumperL(false),
+                BumperR(false),
+                MenuL(false),
+                MenuR(false),
+                Exit(false),
+                Joy(false),
+                Cam(false),
+                JoyX(0.0),
+                JoyY(0.0),
+                CamX(0.0),
+                CamY(0.0),
+                TriggerL(0.0),
+                TriggerR(0.0),
+                Up(false),
+                Down(false),
+                Left(false),
+                Right(false),
+                PovUp(false),
+                PovDown(false),
+                PovLeft(false),
+                PovRight(false),
+            ],
+            Layout::Joystick => {
+                let mut events = vec![
+                    JoyX(0.0),
+                    JoyY(0.0),
+                    Throttle(0.0),
+                    Rudder(0.0),
+                    PovUp(false),
+                    PovDown(false),
+                    PovLeft(false),
+                    PovRight(false),
+                    Trigger(false),
+                    ActionM(false),
+                    Bumper(false),
+                    ActionR(false),
+                    ActionL(false),
+                    Pinky(false),
+                ];
+                events.extend((1..=10).map(|n| Number(n, false)));
+                events
+            }
+            Layout::Custom(events) => events.clone(),
+        }
+    }
+}
+
+/// A virtual controller, which other programs see as real hardware.
+///
+/// Send it events with [`send()`](VirtualController::send()), and `.await`
+/// it to get rumble requests from programs using it (as `(strong, weak)`
+/// power, the same as [`Controller::rumble()`](crate::Controller::rumble)),
+/// or an error if the virtual controller stopped working.
+///
+/// ```rust,no_run
+/// # async fn run(mut controller: stick::Controller) {
+/// use stick::output::{Layout, VirtualController};
+///
+/// let mut output = VirtualController::new("Virtual Pad", Layout::Xbox)
+///     .expect("Couldn't create virtual controller");
+/// loop {
+///     let event = (&mut controller).await;
+///     output.send(event).expect("Virtual controller failed");
+/// }
+/// # }
+/// ```
+pub struct VirtualController(Box<dyn crate::raw::Output>);
+
+impl Debug for VirtualController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "VirtualController")
+    }
+}
+
+impl VirtualController {
+    /// Create a new virtual controller.
+    pub fn new(name: &str, layout: Layout) -> Result<Self, Error> {
+        crate::raw::GLOBAL
+            .with(|g| g.output(name, &layout))
+            .map(Self)
+    }
+
+    /// Send an event, as if it came from hardware.  Events that aren't in the
+    /// controller's layout are ignored.
+    pub fn send(&mut self, event: Event) -> Result<(), Error> {
+        self.0.send(event)
+    }
+}
+
+impl Future for VirtualController {
+    type Output = Result<(f32, f32), Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(f32, f32), Error>> {
+        self.get_mut().0.poll(cx)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_events() {
+        let xbox = Layout::Xbox.events();
+        assert_eq!(xbox.len(), 25);
+        assert!(xbox.iter().any(|e| matches!(e, Event::TriggerR(_))));
+        let joystick = Layout::Joystick.events();
+        assert_eq!(joystick.len(), 24);
+        assert!(matches!(joystick[23], Event::Number(10, _)));
+        let custom = Layout::Custom(vec![Event::Dial(0.0)]).events();
+        assert!(matches!(custom[..], [Event::Dial(_)]));
+    }
+}
@@ -1,8 +1,16 @@
 #![allow(unsafe_code)]
 
-use std::task::{Context, Poll};
+#[cfg(test)]
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use std::{
+    task::{Context, Poll},
+    time::Instant,
+};
 
-use crate::{Event, Remap};
+use crate::{output::Layout, Capabilities, Error, Event, Remap};
 
 #[cfg_attr(
     any(target_arch = "wasm32", target_arch = "asmjs"),
@@ -36,12 +44,13 @@ use crate::{Event, Remap};
 mod ffi;
 
 /// Global state for when the system implementation can fail.
+#[allow(dead_code)] // Not used on all platforms
 struct FakeGlobal;
 
 impl Global for FakeGlobal {}
 
 /// A Listener that never returns any controllers for unsupported platforms.
-struct FakeListener;
+pub(crate) struct FakeListener;
 
 impl Listener for FakeListener {
     fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<crate::Controller> {
@@ -49,10 +58,23 @@ impl Listener for FakeListener {
     }
 }
 
+/// Virtual Controller Implementation
+pub(crate) trait Output: Send {
+    /// Send an event.
+    fn send(&mut self, event: Event) -> Result<(), Error>;
+    /// Poll for rumble requests.
+    fn poll(&mut self, cx: &mut Context<'_>)
+        -> Poll<Result<(f32, f32), Error>>;
+}
+
 /// Controller Listener Implementation
 pub(crate) trait Listener: Send {
     /// Poll for controllers.
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<crate::Controller>;
+    /// Take errors from controllers that couldn't be set up.
+    fn take_errors(&mut self) -> Vec<Error> {
+        Vec::new()
+    }
 }
 
 /// Controller Implementation
@@ -79,6 +101,81 @@ pub(crate) trait Controller: Send {
     fn axis(&self, input: f64) -> f64 {
         input
     }
+    /// Get the current state of every input, as events (before remapping).
+    fn state(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+    /// Grab (or release) exclusive access, returning false on failure.
+    fn grab(&mut self, _grab: bool) -> bool {
+        false
+    }
+    /// Get the inputs (before remapping) and haptic effects supported.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+    /// Start or stop recording platform-specific input.
+    fn trace(&mut self, _enable: bool) {}
+    /// Take recorded platform-specific input (as `Event::Raw`).
+    fn take_trace(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+    /// Get the inputs (before remapping) in the order SDL numbers them.
+    fn sdl_inputs(&self) -> SdlInputs {
+        SdlInputs::default()
+    }
+    /// Get when the last event returned by `poll()` was read.
+    fn time(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A controller that returns queued events, for tests.
+#[cfg(test)]
+#[derive(Default, Clone)]
+pub(crate) struct Mock {
+    pub(crate) id: u64,
+    /// Events to return from `poll()`.
+    pub(crate) events: Arc<Mutex<VecDeque<Event>>>,
+    /// The hardware state, returned from `state()`.
+    pub(crate) state: Arc<Mutex<Vec<Event>>>,
+}
+
+#[cfg(test)]
+impl Mock {
+    /// Queue events to return from `poll()`.
+    pub(crate) fn send(&self, events: impl IntoIterator<Item = Event>) {
+        self.events.lock().unwrap().extend(events);
+    }
+}
+
+#[cfg(test)]
+impl Controller for Mock {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<Event> {
+        match self.events.lock().unwrap().pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+
+    fn state(&mut self) -> Vec<Event> {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+/// A controller's inputs, indexed the way SDL indexes them (for resolving
+/// `b`, `a` and `h` inputs in SDL mappings).
+#[derive(Debug, Default)]
+pub(crate) struct SdlInputs {
+    /// Buttons.
+    pub(crate) buttons: Vec<Event>,
+    /// Axes, with their range.
+    pub(crate) axes: Vec<(Event, (i32, i32))>,
+    /// Hats, as their up, right, down and left directions.
+    pub(crate) hats: Vec<[Event; 4]>,
 }
 
 /// Thread local global state implementation.
@@ -88,8 +185,16 @@ pub(crate) trait Global: std::any::Any {
     /// Disable all events (when window leaves focus).
     fn disable(&self) {}
     /// Create a new listener.
-    fn listener(&self, _remap: Remap) -> Box<dyn Listener> {
-        Box::new(FakeListener)
+    fn listener(&self, _remap: Remap) -> Result<Box<dyn Listener>, Error> {
+        Ok(Box::new(FakeListener))
+    }
+    /// Create a new virtual controller.
+    fn output(
+        &self,
+        _name: &str,
+        _layout: &Layout,
+    ) -> Result<Box<dyn Output>, Error> {
+        Err(Error::Output(std::io::ErrorKind::Unsupported.into()))
     }
 }
 
@@ -1,28 +1,30 @@
 use std::{
     cmp::Ordering,
     fs::read_dir,
+    io,
     mem::{size_of, MaybeUninit},
     os::{
         raw::{c_char, c_int, c_long, c_uint, c_ulong, c_ushort, c_void},
         unix::io::RawFd,
     },
-    task::{Context, Poll},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
 };
 
 use smelling_salts::{Device, Watcher};
 
-use crate::{Event, Remap};
+use crate::{output::Layout, Capabilities, Effect, Error, Event, Remap};
 
 // Event codes taken from
 // https://github.com/torvalds/linux/blob/master/include/uapi/linux/input-event-codes.h
 
 // Convert Linux BTN press to stick Event.
-fn linux_btn_to_stick_event(
-    pending: &mut Vec<Event>,
-    btn: c_ushort,
-    pushed: bool,
-) {
-    pending.push(match btn {
+fn linux_btn_to_stick_event(btn: c_ushort, pushed: bool) -> Option<Event> {
+    Some(match btn {
         0x08B /* KEY_MENU */ => Event::Context(pushed),
 
         0x09E /* KEY_BACK */ => Event::PaddleLeft(pushed),
@@ -109,68 +111,33 @@ fn linux_btn_to_stick_event(
         0x2E6 /* BTN_TRIGGER_HAPPY39 */ => Event::Number(49, pushed),
         0x2E7 /* BTN_TRIGGER_HAPPY40 */ => Event::Number(50, pushed),
 
-        _unknown => {
-            eprintln!("Unknown Linux Button {}", _unknown);
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-            return;
-        }
+        _unknown => return None,
     })
 }
 
 // Convert Linux REL axis to stick Event.
-fn linux_rel_to_stick_event(
-    pending: &mut Vec<Event>,
-    axis: c_ushort,
-    value: c_int,
-) {
-    match axis {
-		0x00 /* REL_X */ => pending.push(Event::MouseX(value as f64)),
-		0x01 /* REL_Y */ => pending.push(Event::MouseY(value as f64)),
-		0x02 /* REL_Z */ => {
-            eprintln!("FIXME: REL_Z");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x03 /* REL_RX */ => {
-            eprintln!("FIXME: REL_RX");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x04 /* REL_RY */ => {
-            eprintln!("FIXME: REL_RY");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x05 /* REL_RZ */ => {
-            eprintln!("FIXME: REL_RZ");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x06 /* REL_HWHEEL */ => {
-            eprintln!("FIXME: REL_HWHEEL");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x07 /* REL_DIAL */ => {
-            eprintln!("FIXME: REL_DIAL");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x08 /* REL_WHEEL */ => {
-            eprintln!("FIXME: REL_WHEEL");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x09 /* REL_MISC */ => {
-            eprintln!("FIXME: REL_MISC");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-        _unknown => {
-            eprintln!("Unknown Linux Axis {}", _unknown);
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-    }
+fn linux_rel_to_stick_event(axis: c_ushort, value: c_int) -> Option<Event> {
+    Some(match axis {
+		0x00 /* REL_X */ => Event::MouseX(value as f64),
+		0x01 /* REL_Y */ => Event::MouseY(value as f64),
+		0x02 /* REL_Z */ => Event::MouseZ(value as f64),
+		0x03 /* REL_RX */ => Event::RotateX(value as f64),
+		0x04 /* REL_RY */ => Event::RotateY(value as f64),
+		0x05 /* REL_RZ */ => Event::RotateZ(value as f64),
+		0x06 /* REL_HWHEEL */ => Event::ScrollX(value as f64),
+		0x07 /* REL_DIAL */ => Event::Dial(value as f64),
+		0x08 /* REL_WHEEL */ => Event::ScrollY(value as f64),
+		0x09 /* REL_MISC */ => Event::Knob(value as f64),
+        _unknown => return None,
+    })
 }
 
-// Convert Linux ABS axis to stick Event.
+// Convert Linux ABS axis to stick Event, returning false if unknown.
 fn linux_abs_to_stick_event(
     pending: &mut Vec<Event>,
     axis: c_ushort,
     value: c_int,
-) {
+) -> bool {
     match axis {
 		0x00 /* ABS_X */ => pending.push(Event::JoyX(value as f64)),
 		0x01 /* ABS_Y */ => pending.push(Event::JoyY(value as f64)),
@@ -186,8 +153,6 @@ fn linux_abs_to_stick_event(
 		0x0B /* ABS_UNKNOWN0 */ => pending.push(Event::Slew(value as f64)),
 		0x0C /* ABS_UNKNOWN1 */ => pending.push(Event::ThrottleL(value as f64)),
 		0x0D /* ABS_UNKNOWN2 */ => pending.push(Event::ThrottleR(value as f64)),
-		0x0E /* ABS_UNKNOWN3 */ => pending.push(Event::ScrollX(value as f64)),
-		0x0F /* ABS_UNKNOWN4 */ => pending.push(Event::ScrollY(value as f64)),
 		0x10 /* ABS_HAT0X */ => match value.cmp(&0) {
             Ordering::Greater => pending.push(Event::PovRight(true)),
             Ordering::Less => pending.push(Event::PovLeft(true)),
@@ -252,60 +217,40 @@ fn linux_abs_to_stick_event(
                 pending.push(Event::MicDown(false));
             }
         },
-		0x18 /* ABS_PRESSURE */ => {
-            eprintln!("Unknown Event: ABS_PRESSURE");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x19 /* ABS_DISTANCE */ => {
-            eprintln!("Unknown Event: ABS_DISTANCE");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x1a /* ABS_TILT_X */ => {
-            eprintln!("Unknown Event: ABS_TILT_X");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x1b /* ABS_TILT_Y */ => {
-            eprintln!("Unknown Event: ABS_TILT_Y");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x1c /* ABS_TOOL_WIDTH */ => {
-            eprintln!("Unknown Event: ABS_TOOL_WIDTH");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x20 /* ABS_VOLUME */ => {
-            eprintln!("Unknown Event: ABS_VOLUME");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-		0x28 /* ABS_MISC */ => {
-            eprintln!("Unknown Event: ABS_MISC");
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
-        _unknown => {
-            eprintln!("Unknown Linux Axis {}", _unknown);
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
-        }
+        // ABS_PRESSURE, ABS_DISTANCE, ABS_TILT_X, ABS_TILT_Y, ABS_TOOL_WIDTH,
+        // ABS_VOLUME, ABS_MISC, etc.
+        _unknown => return false,
     }
+    true
 }
 
-fn linux_evdev_to_stick_event(pending: &mut Vec<Event>, e: EvdevEv) {
+// Create stick Event for an evdev event that isn't otherwise recognized.
+fn raw(kind: c_ushort, code: c_ushort, value: c_int) -> Event {
+    Event::Raw { kind, code, value }
+}
+
+// Convert evdev event to stick Events, returning false if unknown.
+fn linux_evdev_to_stick_event(pending: &mut Vec<Event>, e: &EvdevEv) -> bool {
     match e.ev_type {
         0x00 /* SYN */ => {}, // Ignore Syn Input Events
-        0x01 /* BTN */ => linux_btn_to_stick_event(pending, e.ev_code, e.ev_value != 0),
-        0x02 /* REL */ => linux_rel_to_stick_event(pending, e.ev_code, e.ev_value),
-        0x03 /* ABS */ => linux_abs_to_stick_event(pending, e.ev_code, e.ev_value),
-        0x04 /* MSC */ => {
-            if e.ev_code != 4 { // Ignore Misc./Scan Events
-                let (code, val) = (e.ev_code, e.ev_value);
-                eprintln!("Unknown Linux Misc Code: {}, Value: {}", code, val);
-                eprintln!("Report at https://github.com/ardaku/stick/issues");
+        0x01 /* BTN */ => {
+            match linux_btn_to_stick_event(e.ev_code, e.ev_value != 0) {
+                Some(event) => pending.push(event),
+                None => return false,
             }
         }
-        0x15 /* FF */ => {}, // Ignore Force Feedback Input Events
-        _unknown => {
-            eprintln!("Unknown Linux Event Type: {}", _unknown);
-            eprintln!("Report at https://github.com/ardaku/stick/issues");
+        0x02 /* REL */ => match linux_rel_to_stick_event(e.ev_code, e.ev_value) {
+            Some(event) => pending.push(event),
+            // Ignore REL_WHEEL_HI_RES and REL_HWHEEL_HI_RES (duplicate wheels)
+            None => return e.ev_code == 0x0B || e.ev_code == 0x0C,
         }
+        0x03 /* ABS */ => return linux_abs_to_stick_event(pending, e.ev_code, e.ev_value),
+        // Ignore Misc./Scan and Timestamp Events
+        0x04 /* MSC */ => return e.ev_code == 4 || e.ev_code == 5,
+        0x15 /* FF */ => {}, // Ignore Force Feedback Input Events
+        _unknown => return false,
     }
+    true
 }
 
 #[repr(C)]
@@ -326,6 +271,13 @@ struct TimeVal {
     tv_usec: c_long,
 }
 
+#[repr(C)]
+struct TimeSpec {
+    // struct timespec, from C.
+    tv_sec: c_long,
+    tv_nsec: c_long,
+}
+
 #[repr(C)]
 struct EvdevEv {
     // struct input_event, from C.
@@ -362,6 +314,8 @@ extern "C" {
     fn fcntl(fd: RawFd, cmd: c_int, v: c_int) -> c_int;
     fn ioctl(fd: RawFd, request: c_ulong, v: *mut c_void) -> c_int;
 
+    fn clock_gettime(clock: c_int, time: *mut TimeSpec) -> c_int;
+
     fn inotify_init1(flags: c_int) -> c_int;
     fn inotify_add_watch(fd: RawFd, path: *const u8, mask: u32) -> c_int;
 
@@ -480,17 +434,10 @@ fn joystick_ff(fd: RawFd, code: i16, strong: f32, weak: f32) {
         ev_value: (strong > 0.0 || weak > 0.0) as _,
     };
     let play: *const _ = play;
+    // Haptic feedback is best-effort; errors (such as device unplugged, or
+    // opened read-only) are ignored.
     unsafe {
-        if write(fd, play.cast(), size_of::<EvdevEv>())
-            != size_of::<EvdevEv>() as isize
-        {
-            let errno = *__errno_location();
-            if errno != 19 && errno != 9 {
-                // 19 = device unplugged, ignore
-                // 9 = device openned read-only, ignore
-                panic!("Write exited with {}", *__errno_location());
-            }
-        }
+        write(fd, play.cast(), size_of::<EvdevEv>());
     }
 }
 
@@ -523,6 +470,128 @@ fn joystick_haptic(fd: RawFd, id: i16, strong: f32, weak: f32) -> i16 {
     }
 }
 
+// Get the codes set in the `EVIOCGBIT` bitmask for event type `ev_type`.
+fn evdev_bits<const N: usize>(fd: RawFd, ev_type: u8) -> Vec<c_ushort> {
+    let mut bits = [0u8; N];
+    let request =
+        0x8000_4500 | (N as c_ulong) << 16 | (0x20 + ev_type) as c_ulong;
+    if unsafe { ioctl(fd, request, bits.as_mut_ptr().cast()) } == -1 {
+        return Vec::new();
+    }
+    bits_to_codes(&bits)
+}
+
+// Get the device properties (`INPUT_PROP_*`) set in the `EVIOCGPROP` bitmask.
+fn evdev_props(fd: RawFd) -> Vec<c_ushort> {
+    // INPUT_PROP_CNT = 0x20
+    let mut bits = [0u8; 0x04];
+    if unsafe { ioctl(fd, 0x8004_4509, bits.as_mut_ptr().cast()) } == -1 {
+        return Vec::new();
+    }
+    bits_to_codes(&bits)
+}
+
+fn bits_to_codes(bits: &[u8]) -> Vec<c_ushort> {
+    let mut codes = Vec::new();
+    for (i, byte) in bits.iter().enumerate() {
+        for bit in 0..8 {
+            if byte & (1 << bit) != 0 {
+                codes.push((i * 8 + bit) as c_ushort);
+            }
+        }
+    }
+    codes
+}
+
+// Get a string (`EVIOCGNAME` = 0x06, `EVIOCGPHYS` = 0x07, `EVIOCGUNIQ` = 0x08)
+// from the device.
+fn evdev_string(fd: RawFd, nr: u8) -> io::Result<String> {
+    let mut a = MaybeUninit::<[c_char; 256]>::uninit();
+    let request = 0x80FF_4500 | c_ulong::from(nr);
+    if unsafe { ioctl(fd, request, a.as_mut_ptr().cast()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let a = unsafe { a.assume_init() };
+    let string = unsafe { std::ffi::CStr::from_ptr(a.as_ptr()) };
+    Ok(string.to_string_lossy().to_string())
+}
+
+// Get what identifies the physical device an evdev node is part of (shared
+// between a controller and its companion nodes), if the kernel reports it.
+fn evdev_key(fd: RawFd) -> Option<String> {
+    let phys = evdev_string(fd, 0x07).unwrap_or_default();
+    let uniq = evdev_string(fd, 0x08).unwrap_or_default();
+    if phys.is_empty() && uniq.is_empty() {
+        return None;
+    }
+    Some(format!("{}/{}", phys, uniq))
+}
+
+// Timestamp events with `CLOCK_MONOTONIC` (`EVIOCSCLOCKID`), the clock
+// `Instant` uses, returning false on failure.
+fn evdev_clock(fd: RawFd) -> bool {
+    let mut clock: c_int = 1 /* CLOCK_MONOTONIC */;
+    let clock: *mut c_int = &mut clock;
+    unsafe { ioctl(fd, 0x4004_45A0, clock.cast()) != -1 }
+}
+
+// Convert an event's timestamp to an `Instant` (if the node uses
+// `CLOCK_MONOTONIC`, otherwise it's now).
+fn evdev_time(ev: &EvdevEv, monotonic: bool) -> Instant {
+    let now = Instant::now();
+    let mut time = MaybeUninit::<TimeSpec>::uninit();
+    if !monotonic || unsafe { clock_gettime(1, time.as_mut_ptr()) } == -1 {
+        return now;
+    }
+    let time = unsafe { time.assume_init() };
+    let nanos = |sec: c_long, nsec: c_long| {
+        i128::from(sec) * 1_000_000_000 + i128::from(nsec)
+    };
+    let ago = nanos(time.tv_sec, time.tv_nsec)
+        - nanos(ev.ev_time.tv_sec, ev.ev_time.tv_usec * 1_000);
+    let ago = Duration::from_nanos(u64::try_from(ago).unwrap_or(0));
+    now.checked_sub(ago).unwrap_or(now)
+}
+
+// Read an evdev event, `Ok(None)` if there aren't any new events.
+fn evdev_read(fd: RawFd) -> io::Result<Option<EvdevEv>> {
+    let mut ev = MaybeUninit::<EvdevEv>::uninit();
+    loop {
+        let bytes =
+            unsafe { read(fd, ev.as_mut_ptr().cast(), size_of::<EvdevEv>()) };
+        if bytes == size_of::<EvdevEv>() as isize {
+            return Ok(Some(unsafe { ev.assume_init() }));
+        }
+        if bytes > 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        match unsafe { *__errno_location() } {
+            // EAGAIN: No new events.
+            11 => return Ok(None),
+            // EINTR: Try again.
+            4 => continue,
+            // ENODEV (unplugged), or the device is otherwise unusable.
+            _ => return Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+// Grab (or release) exclusive access to the device with `EVIOCGRAB`.
+fn evdev_grab(fd: RawFd, grab: bool) -> bool {
+    let grab = usize::from(grab) as *mut c_void;
+    unsafe { ioctl(fd, 0x4004_4590, grab) != -1 }
+}
+
+// Get the `EVIOCGABS` range information for an absolute axis.
+fn evdev_abs(fd: RawFd, axis: c_ushort) -> Option<AbsInfo> {
+    let mut a = MaybeUninit::<AbsInfo>::uninit();
+    let request = 0x_8018_4540 + c_ulong::from(axis);
+    if unsafe { ioctl(fd, request, a.as_mut_ptr().cast()) } == -1 {
+        return None;
+    }
+    Some(unsafe { a.assume_init() })
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Gamepad / Other HID
@@ -539,37 +608,61 @@ struct Controller {
     zero: f64,
     /// Don't process near 0
     flat: f64,
-    ///
+    /// Converted events waiting to be returned
     pending_events: Vec<Event>,
-    ///
+    /// Name reported by the kernel
     name: String,
+    /// Whether exclusive access is grabbed
+    grabbed: bool,
+    /// Identifies the physical device, to find companion nodes
+    key: Option<String>,
+    /// Listener this controller came from, which found its companion nodes
+    listener: usize,
+    /// Companion nodes (touchpad, motion sensors)
+    companions: Vec<Companion>,
+    /// Recorded input, if tracing
+    trace: Option<Vec<Event>>,
+    /// Whether events are timestamped with `CLOCK_MONOTONIC`
+    monotonic: bool,
+    /// When the last event was read
+    time: Instant,
 }
 
 impl Controller {
-    fn new(fd: c_int) -> Self {
+    fn new(fd: c_int, listener: usize) -> io::Result<Self> {
         // Enable evdev async.
-        assert_ne!(unsafe { fcntl(fd, 0x4, 0x800) }, -1);
+        if unsafe { fcntl(fd, 0x4, 0x800) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
 
         // Get the hardware id of this controller.
         let mut id = MaybeUninit::<u64>::uninit();
-        assert_ne!(
-            unsafe { ioctl(fd, 0x_8008_4502, id.as_mut_ptr().cast()) },
-            -1
-        );
+        if unsafe { ioctl(fd, 0x_8008_4502, id.as_mut_ptr().cast()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
         let id = unsafe { id.assume_init() }.to_be();
 
-        // Get the min and max absolute values for axis.
-        let mut a = MaybeUninit::<AbsInfo>::uninit();
-        assert_ne!(
-            unsafe { ioctl(fd, 0x_8018_4540, a.as_mut_ptr().cast()) },
-            -1
-        );
-        let a = unsafe { a.assume_init() };
-        let norm = (a.maximum as f64 - a.minimum as f64) * 0.5;
-        let zero = a.minimum as f64 + norm;
-        // Invert so multiplication can be used instead of division
-        let norm = norm.recip();
-        let flat = a.flat as f64 * norm;
+        // Get the min and max absolute values for axis (if it has any).
+        let (norm, zero, flat) = if let Some(a) = evdev_abs(fd, 0x00) {
+            let norm = (a.maximum as f64 - a.minimum as f64) * 0.5;
+            let zero = a.minimum as f64 + norm;
+            // Invert so multiplication can be used instead of division
+            let norm = norm.recip();
+            let flat = a.flat as f64 * norm;
+            (norm, zero, flat)
+        } else {
+            (1.0, 0.0, 0.0)
+        };
+
+        // Get Name
+        let name = evdev_string(fd, 0x06)?;
+        let monotonic = evdev_clock(fd);
+        // Find companion nodes already found by the listener.
+        let key = evdev_key(fd);
+        let companions = match &key {
+            Some(key) => Companion::claim(listener, key, None),
+            None => Vec::new(),
+        };
 
         // Query the controller for haptic support.
         let rumble = joystick_haptic(fd, -1, 0.0, 0.0);
@@ -578,19 +671,8 @@ impl Controller {
         //
         let pending_events = Vec::new();
 
-        // Get Name
-        let fd = device.raw();
-        let mut a = MaybeUninit::<[c_char; 256]>::uninit();
-        assert_ne!(
-            unsafe { ioctl(fd, 0x80FF_4506, a.as_mut_ptr().cast()) },
-            -1
-        );
-        let a = unsafe { a.assume_init() };
-        let name = unsafe { std::ffi::CStr::from_ptr(a.as_ptr()) };
-        let name = name.to_string_lossy().to_string();
-
         // Return
-        Self {
+        Ok(Self {
             device,
             id,
             rumble,
@@ -599,7 +681,14 @@ impl Controller {
             flat,
             pending_events,
             name,
-        }
+            grabbed: false,
+            key,
+            listener,
+            companions,
+            trace: None,
+            monotonic,
+            time: Instant::now(),
+        })
     }
 }
 
@@ -609,9 +698,35 @@ impl super::Controller for Controller {
     }
 
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Event> {
-        // Queue
-        if let Some(e) = self.pending_events.pop() {
-            return Poll::Ready(e);
+        // Queue, in the order events were converted (such as `Accel` before
+        // `Gyro`, and a touch before its position).
+        if !self.pending_events.is_empty() {
+            return Poll::Ready(self.pending_events.remove(0));
+        }
+
+        // Attach companion nodes as the listener finds them.
+        if let Some(key) = &self.key {
+            for companion in Companion::claim(self.listener, key, Some(cx)) {
+                // Already attached (the listener can see a node twice).
+                if self.companions.iter().any(|c| c.path == companion.path) {
+                    continue;
+                }
+                if self.grabbed {
+                    evdev_grab(companion.device.raw(), true);
+                }
+                self.companions.push(companion);
+            }
+        }
+        let mut i = 0;
+        while i < self.companions.len() {
+            let pending = &mut self.pending_events;
+            let (trace, time) = (&mut self.trace, &mut self.time);
+            match self.companions[i].poll(cx, pending, trace, time) {
+                Poll::Ready(true) => return self.poll(cx),
+                // Node stopped working, but the controller might not have.
+                Poll::Ready(false) => drop(self.companions.swap_remove(i)),
+                Poll::Pending => i += 1,
+            }
         }
 
         // Early return if a different device woke the executor.
@@ -620,34 +735,21 @@ impl super::Controller for Controller {
         }
 
         // Read an event.
-        let mut ev = MaybeUninit::<EvdevEv>::uninit();
-        let ev = {
-            let bytes = unsafe {
-                read(
-                    self.device.raw(),
-                    ev.as_mut_ptr().cast(),
-                    size_of::<EvdevEv>(),
-                )
-            };
-            if bytes <= 0 {
-                let errno = unsafe { *__errno_location() };
-                if errno == 19 {
-                    return Poll::Ready(Event::Disconnect);
-                }
-                assert_eq!(errno, 11);
-                // If no new controllers found, return pending.
-                return self.device.sleep(cx);
-            }
-            assert_eq!(size_of::<EvdevEv>() as isize, bytes);
-            unsafe { ev.assume_init() }
+        let ev = match evdev_read(self.device.raw()) {
+            Ok(Some(ev)) => ev,
+            // If no new events, return pending.
+            Ok(None) => return self.device.sleep(cx),
+            Err(_) => return Poll::Ready(Event::Disconnect),
         };
+        self.time = evdev_time(&ev, self.monotonic);
+        if let Some(trace) = &mut self.trace {
+            trace.push(raw(ev.ev_type, ev.ev_code, ev.ev_value));
+        }
 
         // Convert the event (may produce multiple stick events).
-        linux_evdev_to_stick_event(&mut self.pending_events, ev);
-
-        // Check if events should be dropped.
-        if !ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
-            self.pending_events.clear();
+        if !linux_evdev_to_stick_event(&mut self.pending_events, &ev) {
+            let (ev_type, code, value) = (ev.ev_type, ev.ev_code, ev.ev_value);
+            self.pending_events.push(raw(ev_type, code, value));
         }
 
         // Tail call recursion!
@@ -658,6 +760,168 @@ impl super::Controller for Controller {
         &self.name
     }
 
+    fn state(&mut self) -> Vec<Event> {
+        let fd = self.device.raw();
+        let mut events = Vec::new();
+
+        // EVIOCGKEY (KEY_CNT = 0x300)
+        let mut keys = [0u8; 0x60];
+        if unsafe { ioctl(fd, 0x8060_4518, keys.as_mut_ptr().cast()) } != -1 {
+            for code in evdev_bits::<0x60>(fd, 0x01 /* EV_KEY */) {
+                let byte = keys[usize::from(code / 8)];
+                let pushed = byte & (1 << (code % 8)) != 0;
+                events.extend(linux_btn_to_stick_event(code, pushed));
+            }
+        }
+        for code in evdev_bits::<0x08>(fd, 0x03 /* EV_ABS */) {
+            if let Some(a) = evdev_abs(fd, code) {
+                linux_abs_to_stick_event(&mut events, code, a.value);
+            }
+        }
+
+        events
+    }
+
+    fn grab(&mut self, grab: bool) -> bool {
+        for companion in &self.companions {
+            evdev_grab(companion.device.raw(), grab);
+        }
+        let success = evdev_grab(self.device.raw(), grab);
+        self.grabbed = grab && success;
+        success
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let fd = self.device.raw();
+        let mut caps = Capabilities::default();
+
+        // KEY_CNT = 0x300
+        for code in evdev_bits::<0x60>(fd, 0x01 /* EV_KEY */) {
+            match linux_btn_to_stick_event(code, false) {
+                Some(event) => caps.input(event, None),
+                None => caps.input(raw(0x01, code, 0), None),
+            }
+        }
+        // REL_CNT = 0x10 (relative axes report deltas, with no fixed range)
+        for code in evdev_bits::<0x02>(fd, 0x02 /* EV_REL */) {
+            if code == 0x0B || code == 0x0C {
+                continue; // Hi-res wheels
+            }
+            let event = linux_rel_to_stick_event(code, 0);
+            caps.input(event.unwrap_or(raw(0x02, code, 0)), Some((-1, 1)));
+        }
+        // ABS_CNT = 0x40
+        for code in evdev_bits::<0x08>(fd, 0x03 /* EV_ABS */) {
+            let range = evdev_abs(fd, code).map(|a| (a.minimum, a.maximum));
+            let mut events = Vec::new();
+            if !linux_abs_to_stick_event(&mut events, code, 0) {
+                events.push(raw(0x03, code, 0));
+            }
+            for event in events {
+                caps.input(event, range);
+            }
+        }
+        // FF_CNT = 0x80
+        for code in evdev_bits::<0x10>(fd, 0x15 /* EV_FF */) {
+            caps.effect(match code {
+                0x50 /* FF_RUMBLE */ => Effect::Rumble,
+                0x51 /* FF_PERIODIC */ => Effect::Periodic,
+                0x52 /* FF_CONSTANT */ => Effect::Constant,
+                0x53 /* FF_SPRING */ => Effect::Spring,
+                0x54 /* FF_FRICTION */ => Effect::Friction,
+                0x55 /* FF_DAMPER */ => Effect::Damper,
+                0x56 /* FF_INERTIA */ => Effect::Inertia,
+                0x57 /* FF_RAMP */ => Effect::Ramp,
+                0x58 /* FF_SQUARE */ => Effect::Square,
+                0x59 /* FF_TRIANGLE */ => Effect::Triangle,
+                0x5A /* FF_SINE */ => Effect::Sine,
+                0x5B /* FF_SAW_UP */ => Effect::SawUp,
+                0x5C /* FF_SAW_DOWN */ => Effect::SawDown,
+                0x5D /* FF_CUSTOM */ => Effect::Custom,
+                0x60 /* FF_GAIN */ => Effect::Gain,
+                0x61 /* FF_AUTOCENTER */ => Effect::Autocenter,
+                _unknown => continue,
+            });
+        }
+        for companion in &self.companions {
+            companion.capabilities(&mut caps);
+        }
+
+        caps
+    }
+
+    fn trace(&mut self, enable: bool) {
+        if !enable {
+            self.trace = None;
+        } else if self.trace.is_none() {
+            self.trace = Some(Vec::new());
+        }
+    }
+
+    fn take_trace(&mut self) -> Vec<Event> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn time(&self) -> Instant {
+        self.time
+    }
+
+    fn sdl_inputs(&self) -> super::SdlInputs {
+        let fd = self.device.raw();
+        let mut inputs = super::SdlInputs::default();
+
+        // SDL numbers joystick buttons (from BTN_JOYSTICK) before the others.
+        let (joystick, other): (Vec<_>, Vec<_>) =
+            evdev_bits::<0x60>(fd, 0x01 /* EV_KEY */)
+                .into_iter()
+                .partition(|code| *code >= 0x120 /* BTN_JOYSTICK */);
+        for code in joystick.into_iter().chain(other) {
+            let event = linux_btn_to_stick_event(code, false);
+            inputs.buttons.push(event.unwrap_or(raw(0x01, code, 0)));
+        }
+        // Hats are numbered separately from the other axes.
+        let abs = evdev_bits::<0x08>(fd, 0x03 /* EV_ABS */);
+        for &code in &abs {
+            if (0x10..=0x17).contains(&code) {
+                continue; // ABS_HAT0X..=ABS_HAT3Y
+            }
+            let mut events = Vec::new();
+            if !linux_abs_to_stick_event(&mut events, code, 0) {
+                events.push(raw(0x03, code, 0));
+            }
+            let range = evdev_abs(fd, code)
+                .map(|a| (a.minimum, a.maximum))
+                .unwrap_or_default();
+            inputs.axes.extend(events.into_iter().map(|e| (e, range)));
+        }
+        for hat in 0..4 {
+            let x = 0x10 + hat * 2;
+            if !abs.contains(&x) && !abs.contains(&(x + 1)) {
+                continue;
+            }
+            use Event::*;
+            inputs.hats.push(match hat {
+                0 => {
+                    [PovUp(true), PovRight(true), PovDown(true), PovLeft(true)]
+                }
+                1 => {
+                    [HatUp(true), HatRight(true), HatDown(true), HatLeft(true)]
+                }
+                2 => [
+                    TrimUp(true),
+                    TrimRight(true),
+                    TrimDown(true),
+                    TrimLeft(true),
+                ],
+                _ => {
+                    [MicUp(true), MicRight(true), MicDown(true), MicLeft(true)]
+                }
+            });
+        }
+
+        inputs
+    }
+
     fn rumble(&mut self, left: f32, right: f32) {
         if self.rumble >= 0 {
             joystick_ff(self.device.raw(), self.rumble, left, right);
@@ -682,18 +946,378 @@ impl super::Controller for Controller {
 
 impl Drop for Controller {
     fn drop(&mut self) {
-        assert_ne!(unsafe { close(self.device.stop()) }, -1);
+        if self.grabbed {
+            evdev_grab(self.device.raw(), false);
+        }
+        // Nothing can be done if closing fails.
+        unsafe { close(self.device.stop()) };
+    }
+}
+
+/// Companion nodes found by a listener, waiting to be attached to their
+/// controller.
+static COMPANIONS: Mutex<Companions> = Mutex::new(Companions {
+    companions: Vec::new(),
+    wakers: Vec::new(),
+});
+
+struct Companions {
+    companions: Vec<Companion>,
+    // Controllers waiting for their companion nodes.
+    wakers: Vec<Waker>,
+}
+
+/// Separate evdev node that's a part of a controller (such as a touchpad)
+struct Companion {
+    // Async device handle
+    device: Device,
+    /// Path of the evdev node
+    path: String,
+    /// Identifies the physical device, shared with the controller
+    key: String,
+    /// Listener that found the node
+    listener: usize,
+    /// Whether events are timestamped with `CLOCK_MONOTONIC`
+    monotonic: bool,
+    /// What the node is
+    kind: Kind,
+}
+
+enum Kind {
+    Touchpad(Touchpad),
+    Motion(Motion),
+}
+
+impl Companion {
+    // Check if an evdev node is part of another device, and return its key.
+    fn detect(fd: RawFd) -> Option<String> {
+        let props = evdev_props(fd);
+        let abs = evdev_bits::<0x08>(fd, 0x03 /* EV_ABS */);
+        // INPUT_PROP_BUTTONPAD with ABS_MT_SLOT, or INPUT_PROP_ACCELEROMETER
+        let touchpad = props.contains(&0x02) && abs.contains(&0x2F);
+        if !touchpad && !props.contains(&0x06) {
+            return None;
+        }
+        evdev_key(fd)
+    }
+
+    // Check if another evdev node (that isn't a companion node) has the same
+    // key, so nodes of other devices (such as laptop touchpads) are skipped.
+    fn has_controller(path: &str, key: &str) -> bool {
+        let Ok(nodes) = read_dir("/sys/class/input/") else {
+            return false;
+        };
+        let node = path.rsplit('/').next().unwrap_or_default();
+        nodes.flatten().any(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with("event") || name == node {
+                return false;
+            }
+            let device = entry.path().join("device");
+            let read = |attr| {
+                let text = std::fs::read_to_string(device.join(attr));
+                text.unwrap_or_default().trim_end().to_string()
+            };
+            // Properties are a bitmap, in hexadecimal words (lowest last).
+            let props = read("properties");
+            let props = props.split_whitespace().last().unwrap_or("0");
+            let props = u64::from_str_radix(props, 16).unwrap_or(0);
+            // INPUT_PROP_BUTTONPAD or INPUT_PROP_ACCELEROMETER
+            let companion = props & (1 << 0x02 | 1 << 0x06) != 0;
+            !companion && format!("{}/{}", read("phys"), read("uniq")) == key
+        })
+    }
+
+    fn new(
+        fd: RawFd,
+        path: String,
+        key: String,
+        listener: usize,
+    ) -> io::Result<Self> {
+        // Enable evdev async.
+        if unsafe { fcntl(fd, 0x4, 0x800) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let kind = if evdev_props(fd).contains(&0x06) {
+            Kind::Motion(Motion::new(fd))
+        } else {
+            Kind::Touchpad(Touchpad::new(fd))
+        };
+        let monotonic = evdev_clock(fd);
+        let device = Device::new(fd, Watcher::new().input());
+
+        Ok(Self {
+            device,
+            path,
+            key,
+            listener,
+            monotonic,
+            kind,
+        })
+    }
+
+    // Make available for the controller it's a part of to claim.
+    fn park(self) {
+        let mut companions = COMPANIONS.lock().unwrap();
+        // Already parked (the listener can see a node twice).
+        if companions
+            .companions
+            .iter()
+            .any(|c| c.listener == self.listener && c.path == self.path)
+        {
+            return;
+        }
+        companions.companions.push(self);
+        for waker in companions.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    // Close the companion nodes `listener` found that weren't claimed.
+    fn release(listener: usize) {
+        let mut companions = COMPANIONS.lock().unwrap();
+        companions.companions.retain(|c| c.listener != listener);
+    }
+
+    // Claim the companion nodes `listener` found for the controller with
+    // `key`, and (if there's a context) get woken when more are found.
+    fn claim(
+        listener: usize,
+        key: &str,
+        cx: Option<&Context<'_>>,
+    ) -> Vec<Self> {
+        let mut companions = COMPANIONS.lock().unwrap();
+        let mut claimed = Vec::new();
+        while let Some(i) = companions
+            .companions
+            .iter()
+            .position(|c| c.listener == listener && c.key == key)
+        {
+            claimed.push(companions.companions.swap_remove(i));
+        }
+        if let Some(cx) = cx {
+            if !companions.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                companions.wakers.push(cx.waker().clone());
+            }
+        }
+        claimed
+    }
+
+    // Returns `Ready(false)` if the node stopped working.
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+        pending: &mut Vec<Event>,
+        trace: &mut Option<Vec<Event>>,
+        time: &mut Instant,
+    ) -> Poll<bool> {
+        loop {
+            // Early return if a different device woke the executor.
+            if self.device.pending() {
+                return self.device.sleep(cx);
+            }
+            match evdev_read(self.device.raw()) {
+                Ok(Some(ev)) => {
+                    *time = evdev_time(&ev, self.monotonic);
+                    if let Some(trace) = trace {
+                        trace.push(raw(ev.ev_type, ev.ev_code, ev.ev_value));
+                    }
+                    match &mut self.kind {
+                        Kind::Touchpad(touchpad) => {
+                            pending.extend(touchpad.convert(&ev));
+                        }
+                        Kind::Motion(motion) => motion.convert(pending, &ev),
+                    }
+                    if !pending.is_empty() {
+                        return Poll::Ready(true);
+                    }
+                }
+                Ok(None) => return self.device.sleep(cx),
+                Err(_) => return Poll::Ready(false),
+            }
+        }
+    }
+
+    fn capabilities(&self, caps: &mut Capabilities) {
+        let fd = self.device.raw();
+        match self.kind {
+            Kind::Touchpad(_) => Touchpad::capabilities(fd, caps),
+            Kind::Motion(_) => Motion::capabilities(fd, caps),
+        }
+    }
+}
+
+impl Drop for Companion {
+    fn drop(&mut self) {
+        // Nothing can be done if closing fails.
+        unsafe { close(self.device.stop()) };
+    }
+}
+
+/// Multitouch touchpad
+struct Touchpad {
+    /// Multitouch slot that following events are for
+    slot: c_int,
+    /// Horizontal position minimum and multiplier
+    x: (f64, f64),
+    /// Vertical position minimum and multiplier
+    y: (f64, f64),
+}
+
+impl Touchpad {
+    fn new(fd: RawFd) -> Self {
+        // Get the ranges of ABS_MT_POSITION_X and ABS_MT_POSITION_Y.
+        let range = |axis| {
+            let a = evdev_abs(fd, axis)?;
+            let size = f64::from(a.maximum) - f64::from(a.minimum);
+            Some((f64::from(a.minimum), size.recip()))
+        };
+        let x = range(0x35).unwrap_or((0.0, 1.0));
+        let y = range(0x36).unwrap_or((0.0, 1.0));
+        let slot = evdev_abs(fd, 0x2F).map_or(0, |a| a.value);
+
+        Self { slot, x, y }
+    }
+
+    // Convert multitouch (protocol B) events, ignoring the rest since they
+    // duplicate the contacts (such as `BTN_TOUCH` and `ABS_X`).
+    fn convert(&mut self, e: &EvdevEv) -> Option<Event> {
+        let n = u8::try_from(self.slot).ok()?;
+        let value = f64::from(e.ev_value);
+        Some(match (e.ev_type, e.ev_code) {
+            (0x01 /* BTN */, 0x110 /* BTN_LEFT */) => {
+                Event::Touchpad(e.ev_value != 0)
+            }
+            (0x03 /* ABS */, 0x2F /* ABS_MT_SLOT */) => {
+                self.slot = e.ev_value;
+                return None;
+            }
+            (0x03 /* ABS */, 0x39 /* ABS_MT_TRACKING_ID */) => {
+                Event::Touch(n, e.ev_value != -1)
+            }
+            (0x03 /* ABS */, 0x35 /* ABS_MT_POSITION_X */) => {
+                let (min, norm) = self.x;
+                Event::TouchX(n, ((value - min) * norm).clamp(0.0, 1.0))
+            }
+            (0x03 /* ABS */, 0x36 /* ABS_MT_POSITION_Y */) => {
+                let (min, norm) = self.y;
+                Event::TouchY(n, ((value - min) * norm).clamp(0.0, 1.0))
+            }
+            _ => return None,
+        })
+    }
+
+    fn capabilities(fd: RawFd, caps: &mut Capabilities) {
+        if evdev_bits::<0x60>(fd, 0x01 /* EV_KEY */).contains(&0x110) {
+            caps.input(Event::Touchpad(false), None);
+        }
+        if let Some(a) = evdev_abs(fd, 0x2F /* ABS_MT_SLOT */) {
+            let slots = a.maximum.clamp(0, u8::MAX.into()) as u8;
+            caps.input(Event::Touch(slots, false), None);
+        }
+    }
+}
+
+/// Accelerometer and gyroscope
+struct Motion {
+    /// Multipliers to convert `ABS_X` through `ABS_RZ` to physical units
+    scale: [f64; 6],
+    /// Latest values of `ABS_X` through `ABS_RZ`, in physical units
+    values: [f64; 6],
+    /// Whether the accelerometer and gyroscope changed since the last report
+    changed: (bool, bool),
+}
+
+impl Motion {
+    fn new(fd: RawFd) -> Self {
+        // Resolution is in units per g for the accelerometer (`ABS_X`,
+        // `ABS_Y`, `ABS_Z`), and units per degree per second for the
+        // gyroscope (`ABS_RX`, `ABS_RY`, `ABS_RZ`).
+        const G: f64 = 9.80665;
+        const DEG: f64 = std::f64::consts::PI / 180.0;
+
+        let mut scale = [0.0; 6];
+        let mut values = [0.0; 6];
+        for axis in 0..6 {
+            let unit = if axis < 3 { G } else { DEG };
+            let Some(a) = evdev_abs(fd, axis) else {
+                continue;
+            };
+            scale[usize::from(axis)] = if a.resolution > 0 {
+                unit / f64::from(a.resolution)
+            } else {
+                unit
+            };
+            values[usize::from(axis)] =
+                f64::from(a.value) * scale[usize::from(axis)];
+        }
+
+        Self {
+            scale,
+            values,
+            changed: (false, false),
+        }
+    }
+
+    // Convert motion events, sending each sensor's axes together once the
+    // hardware finishes a report (`SYN_REPORT`).
+    fn convert(&mut self, pending: &mut Vec<Event>, e: &EvdevEv) {
+        match (e.ev_type, e.ev_code) {
+            (0x00 /* SYN */, 0x00 /* SYN_REPORT */) => {
+                let [x, y, z, rx, ry, rz] = self.values;
+                if std::mem::take(&mut self.changed.0) {
+                    pending.push(Event::Accel { x, y, z });
+                }
+                if std::mem::take(&mut self.changed.1) {
+                    pending.push(Event::Gyro {
+                        x: rx,
+                        y: ry,
+                        z: rz,
+                    });
+                }
+            }
+            (0x03 /* ABS */, axis @ 0x00..=0x05) => {
+                let axis = usize::from(axis);
+                self.values[axis] = f64::from(e.ev_value) * self.scale[axis];
+                if axis < 3 {
+                    self.changed.0 = true;
+                } else {
+                    self.changed.1 = true;
+                }
+            }
+            // Ignore the rest (such as `MSC_TIMESTAMP`).
+            _ => {}
+        }
+    }
+
+    fn capabilities(fd: RawFd, caps: &mut Capabilities) {
+        let abs = evdev_bits::<0x08>(fd, 0x03 /* EV_ABS */);
+        let (x, y, z) = (0.0, 0.0, 0.0);
+        if abs.contains(&0x00) {
+            caps.input(Event::Accel { x, y, z }, None);
+        }
+        if abs.contains(&0x03) {
+            caps.input(Event::Gyro { x, y, z }, None);
+        }
     }
 }
 
+/// Source of unique listener identifiers.
+static LISTENERS: AtomicUsize = AtomicUsize::new(0);
+
 struct Listener {
     device: Device,
     read_dir: Option<Box<std::fs::ReadDir>>,
     remap: Remap,
+    errors: Vec<Error>,
+    // Identifies the companion nodes this listener found
+    id: usize,
 }
 
 impl Listener {
-    fn new(remap: Remap) -> Self {
+    fn new(remap: Remap) -> Result<Self, Error> {
         const CLOEXEC: c_int = 0o2000000;
         const NONBLOCK: c_int = 0o0004000;
         const ATTRIB: c_uint = 0x00000004;
@@ -702,28 +1326,40 @@ impl Listener {
         // Create an inotify.
         let listen = unsafe { inotify_init1(NONBLOCK | CLOEXEC) };
         if listen == -1 {
-            panic!("Couldn't create inotify!");
+            return Err(Error::Listener(io::Error::last_os_error()));
         }
 
         // Start watching the controller directory.
         if unsafe { inotify_add_watch(listen, DIR.as_ptr(), ATTRIB) } == -1 {
-            panic!("Couldn't add inotify watch!");
+            let error = io::Error::last_os_error();
+            unsafe { close(listen) };
+            return Err(Error::Listener(error));
         }
 
-        Self {
+        // Find controllers that are already plugged in.
+        let read_dir = match read_dir("/dev/input/") {
+            Ok(read_dir) => read_dir,
+            Err(error) => {
+                unsafe { close(listen) };
+                return Err(Error::Listener(error));
+            }
+        };
+
+        Ok(Self {
             // Create watcher, and register with fd as a "device".
             device: Device::new(listen, Watcher::new().input()),
             //
-            read_dir: Some(Box::new(read_dir("/dev/input/").unwrap())),
+            read_dir: Some(Box::new(read_dir)),
             //
             remap,
-        }
+            //
+            errors: Vec::new(),
+            //
+            id: LISTENERS.fetch_add(1, AtomicOrdering::Relaxed),
+        })
     }
 
-    fn controller(
-        remap: &Remap,
-        mut filename: String,
-    ) -> Poll<crate::Controller> {
+    fn controller(&mut self, mut filename: String) -> Poll<crate::Controller> {
         if filename.contains("event") {
             filename.push('\0');
             // Try read & write first
@@ -738,10 +1374,36 @@ impl Listener {
             }
             // If one succeeded, return that controller.
             if fd != -1 {
-                return Poll::Ready(crate::Controller::new(
-                    Box::new(Controller::new(fd)),
-                    remap,
-                ));
+                filename.pop();
+                // Companion nodes are attached to their controller instead
+                // (and nodes of other devices skipped).
+                if let Some(key) = Companion::detect(fd) {
+                    if !Companion::has_controller(&filename, &key) {
+                        unsafe { close(fd) };
+                        return Poll::Pending;
+                    }
+                    match Companion::new(fd, filename.clone(), key, self.id) {
+                        Ok(companion) => companion.park(),
+                        Err(error) => {
+                            unsafe { close(fd) };
+                            self.errors
+                                .push(Error::Controller(filename, error));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+                match Controller::new(fd, self.id) {
+                    Ok(controller) => {
+                        return Poll::Ready(crate::Controller::new(
+                            Box::new(controller),
+                            &self.remap,
+                        ));
+                    }
+                    Err(error) => {
+                        unsafe { close(fd) };
+                        self.errors.push(Error::Controller(filename, error));
+                    }
+                }
             }
         }
         Poll::Pending
@@ -751,17 +1413,15 @@ impl Listener {
 impl super::Listener for Listener {
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<crate::Controller> {
         // Read the directory for ctrls if initialization hasn't completed yet.
-        if let Some(ref mut read_dir) = &mut self.read_dir {
-            for dir_entry in read_dir.flatten() {
+        if let Some(mut read_dir) = self.read_dir.take() {
+            for dir_entry in read_dir.by_ref().flatten() {
                 let file = dir_entry.path();
                 let path = file.as_path().to_string_lossy().to_string();
-                if let Poll::Ready(controller) =
-                    Self::controller(&self.remap, path)
-                {
+                if let Poll::Ready(controller) = self.controller(path) {
+                    self.read_dir = Some(read_dir);
                     return Poll::Ready(controller);
                 }
             }
-            self.read_dir = None;
         }
 
         // Read the Inotify Event.
@@ -778,8 +1438,7 @@ impl super::Listener for Listener {
             let len = unsafe { strlen(&ev.name[0]) };
             let filename = String::from_utf8_lossy(&ev.name[..len]);
             let path = format!("/dev/input/{}", filename);
-            if let Poll::Ready(controller) = Self::controller(&self.remap, path)
-            {
+            if let Poll::Ready(controller) = self.controller(path) {
                 return Poll::Ready(controller);
             }
         }
@@ -787,36 +1446,581 @@ impl super::Listener for Listener {
         // Register waker & go to sleep for this device
         self.device.sleep(cx)
     }
+
+    fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
 }
 
 impl Drop for Listener {
     fn drop(&mut self) {
-        assert_eq!(unsafe { close(self.device.stop()) }, 0);
+        Companion::release(self.id);
+        // Nothing can be done if closing fails.
+        unsafe { close(self.device.stop()) };
     }
 }
 
-static ENABLED: std::sync::atomic::AtomicBool =
-    std::sync::atomic::AtomicBool::new(true);
+// How a stick Event is written to uinput.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Code {
+    Key(c_ushort),
+    // Whether the axis is centered (-1 to 1), rather than from 0 to 1.
+    Abs(c_ushort, bool),
+    Rel(c_ushort),
+    // Hat axis, and which direction (-1 or 1).
+    Hat(c_ushort, c_int),
+}
 
-struct Global;
+// Convert stick Event to Linux code (inverse of the `linux_*_to_stick_event`
+// functions).
+fn stick_event_to_linux(event: Event) -> Option<Code> {
+    use Code::{Abs, Hat, Key, Rel};
+    Some(match event {
+        Event::Context(_) => Key(0x08B /* KEY_MENU */),
+        Event::PaddleLeft(_) => Key(0x09E /* KEY_BACK */),
+        Event::PaddleRight(_) => Key(0x09F /* KEY_FORWARD */),
+
+        Event::Trigger(_) => Key(0x120 /* BTN_TRIGGER */),
+        Event::ActionM(_) => Key(0x121 /* BTN_THUMB */),
+        Event::Bumper(_) => Key(0x122 /* BTN_THUMB2 */),
+        Event::ActionR(_) => Key(0x123 /* BTN_TOP */),
+        Event::ActionL(_) => Key(0x124 /* BTN_TOP2 */),
+        Event::Pinky(_) => Key(0x125 /* BTN_PINKIE */),
+        Event::Number(n @ 1..=10, _) => Key(0x125 + n as c_ushort),
+        Event::Number(n @ 11..=50, _) => Key(0x2B5 + n as c_ushort),
+
+        Event::ActionA(_) => Key(0x130 /* BTN_A / BTN_SOUTH */),
+        Event::ActionB(_) => Key(0x131 /* BTN_B / BTN_EAST */),
+        Event::ActionC(_) => Key(0x132 /* BTN_C */),
+        Event::ActionV(_) => Key(0x133 /* BTN_X / BTN_NORTH */),
+        Event::ActionH(_) => Key(0x134 /* BTN_Y / BTN_WEST */),
+        Event::ActionD(_) => Key(0x135 /* BTN_Z */),
+        Event::BumperL(_) => Key(0x136 /* BTN_TL */),
+        Event::BumperR(_) => Key(0x137 /* BTN_TR */),
+        Event::MenuL(_) => Key(0x13A /* BTN_SELECT */),
+        Event::MenuR(_) => Key(0x13B /* BTN_START */),
+        Event::Exit(_) => Key(0x13C /* BTN_MODE */),
+        Event::Joy(_) => Key(0x13D /* BTN_THUMBL */),
+        Event::Cam(_) => Key(0x13E /* BTN_THUMBR */),
+        Event::PinkyRight(_) => Key(0x13F /* BTN_PINKYR */),
+        Event::PinkyLeft(_) => Key(0x140 /* BTN_PINKYL */),
+
+        Event::Up(_) => Key(0x220 /* BTN_DPAD_UP */),
+        Event::Down(_) => Key(0x221 /* BTN_DPAD_DOWN */),
+        Event::Left(_) => Key(0x222 /* BTN_DPAD_LEFT */),
+        Event::Right(_) => Key(0x223 /* BTN_DPAD_RIGHT */),
+
+        Event::JoyX(_) => Abs(0x00 /* ABS_X */, true),
+        Event::JoyY(_) => Abs(0x01 /* ABS_Y */, true),
+        Event::JoyZ(_) => Abs(0x02 /* ABS_Z */, true),
+        Event::TriggerL(_) => Abs(0x02 /* ABS_Z */, false),
+        Event::CamX(_) => Abs(0x03 /* ABS_RX */, true),
+        Event::CamY(_) => Abs(0x04 /* ABS_RY */, true),
+        Event::CamZ(_) => Abs(0x05 /* ABS_RZ */, true),
+        Event::TriggerR(_) => Abs(0x05 /* ABS_RZ */, false),
+        Event::Throttle(_) => Abs(0x06 /* ABS_THROTTLE */, false),
+        Event::Rudder(_) => Abs(0x07 /* ABS_RUDDER */, false),
+        Event::Wheel(_) => Abs(0x08 /* ABS_WHEEL */, false),
+        Event::Gas(_) => Abs(0x09 /* ABS_GAS */, false),
+        Event::Brake(_) => Abs(0x0A /* ABS_BRAKE */, false),
+        Event::Slew(_) => Abs(0x0B /* ABS_UNKNOWN0 */, false),
+        Event::ThrottleL(_) => Abs(0x0C /* ABS_UNKNOWN1 */, false),
+        Event::ThrottleR(_) => Abs(0x0D /* ABS_UNKNOWN2 */, false),
+
+        Event::PovLeft(_) => Hat(0x10 /* ABS_HAT0X */, -1),
+        Event::PovRight(_) => Hat(0x10 /* ABS_HAT0X */, 1),
+        Event::PovUp(_) => Hat(0x11 /* ABS_HAT0Y */, -1),
+        Event::PovDown(_) => Hat(0x11 /* ABS_HAT0Y */, 1),
+        Event::HatLeft(_) => Hat(0x12 /* ABS_HAT1X */, -1),
+        Event::HatRight(_) => Hat(0x12 /* ABS_HAT1X */, 1),
+        Event::HatUp(_) => Hat(0x13 /* ABS_HAT1Y */, -1),
+        Event::HatDown(_) => Hat(0x13 /* ABS_HAT1Y */, 1),
+        Event::TrimLeft(_) => Hat(0x14 /* ABS_HAT2X */, -1),
+        Event::TrimRight(_) => Hat(0x14 /* ABS_HAT2X */, 1),
+        Event::TrimUp(_) => Hat(0x15 /* ABS_HAT2Y */, -1),
+        Event::TrimDown(_) => Hat(0x15 /* ABS_HAT2Y */, 1),
+        Event::MicLeft(_) => Hat(0x16 /* ABS_HAT3X */, -1),
+        Event::MicRight(_) => Hat(0x16 /* ABS_HAT3X */, 1),
+        Event::MicUp(_) => Hat(0x17 /* ABS_HAT3Y */, -1),
+        Event::MicDown(_) => Hat(0x17 /* ABS_HAT3Y */, 1),
+
+        Event::MouseX(_) => Rel(0x00 /* REL_X */),
+        Event::MouseY(_) => Rel(0x01 /* REL_Y */),
+        Event::MouseZ(_) => Rel(0x02 /* REL_Z */),
+        Event::RotateX(_) => Rel(0x03 /* REL_RX */),
+        Event::RotateY(_) => Rel(0x04 /* REL_RY */),
+        Event::RotateZ(_) => Rel(0x05 /* REL_RZ */),
+        Event::ScrollX(_) => Rel(0x06 /* REL_HWHEEL */),
+        Event::Dial(_) => Rel(0x07 /* REL_DIAL */),
+        Event::ScrollY(_) => Rel(0x08 /* REL_WHEEL */),
+        Event::Knob(_) => Rel(0x09 /* REL_MISC */),
+
+        _unsupported => return None,
+    })
+}
 
-impl super::Global for Global {
-    /// Enable all events (when window comes in focus).
-    fn enable(&self) {
-        ENABLED.store(true, std::sync::atomic::Ordering::Relaxed);
+// Get the codes a virtual controller with `layout` uses.  Custom layouts
+// must only have events that can be sent.
+fn layout_codes(layout: &Layout) -> io::Result<Vec<Code>> {
+    let dpad_hat = matches!(layout, Layout::Xbox);
+    let events = layout.events();
+    if events.is_empty() {
+        let error = "Custom layout has no events";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+    }
+    let mut codes = Vec::new();
+    for event in events {
+        let Some(code) = stick_event_to_linux(dpad(event, dpad_hat)) else {
+            if matches!(layout, Layout::Custom(_)) {
+                let error = format!("{} can't be sent by uinput", event);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+            }
+            continue;
+        };
+        // Axes can only be used by one event (the first in the layout).
+        let used = codes.iter().any(|used| match (used, code) {
+            (Code::Abs(a, _), Code::Abs(b, _)) => *a == b,
+            (used, code) => *used == code,
+        });
+        if !used {
+            codes.push(code);
+        }
+    }
+    Ok(codes)
+}
+
+// Convert d-pad events to the first hat's, if `hat`.
+fn dpad(event: Event, hat: bool) -> Event {
+    match event {
+        Event::Up(p) if hat => Event::PovUp(p),
+        Event::Down(p) if hat => Event::PovDown(p),
+        Event::Left(p) if hat => Event::PovLeft(p),
+        Event::Right(p) if hat => Event::PovRight(p),
+        event => event,
+    }
+}
+
+// From: https://github.com/torvalds/linux/blob/master/include/uapi/linux/uinput.h
+
+#[repr(C)]
+struct InputId {
+    // struct input_id, from C.
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+struct UinputUserDev {
+    // struct uinput_user_dev, from C.
+    name: [c_char; 80],
+    id: InputId,
+    ff_effects_max: u32,
+    absmax: [i32; 0x40],
+    absmin: [i32; 0x40],
+    absfuzz: [i32; 0x40],
+    absflat: [i32; 0x40],
+}
+
+#[repr(C)]
+struct UinputFfUpload {
+    // struct uinput_ff_upload, from C.
+    request_id: u32,
+    retval: i32,
+    effect: FfEffect,
+    old: FfEffect,
+}
+
+#[repr(C)]
+struct UinputFfErase {
+    // struct uinput_ff_erase, from C.
+    request_id: u32,
+    retval: i32,
+    effect_id: u32,
+}
+
+// Set a bit (`UI_SET_*BIT`) on a uinput device before it's created.
+fn uinput_bit(fd: RawFd, request: c_ulong, bit: c_ushort) -> io::Result<()> {
+    let bit = usize::from(bit) as *mut c_void;
+    if unsafe { ioctl(fd, 0x4004_5500 | request, bit) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Write an event, followed by `SYN_REPORT`.
+fn uinput_write(
+    fd: RawFd,
+    ev_type: c_ushort,
+    ev_code: c_ushort,
+    ev_value: c_int,
+) -> io::Result<()> {
+    let event = |ev_type, ev_code, ev_value| EvdevEv {
+        ev_time: TimeVal {
+            tv_sec: 0,
+            tv_usec: 0,
+        },
+        ev_type,
+        ev_code,
+        ev_value,
+    };
+    let events = [event(ev_type, ev_code, ev_value), event(0x00, 0x00, 0)];
+    let size = size_of::<[EvdevEv; 2]>();
+    if unsafe { write(fd, events.as_ptr().cast(), size) } != size as isize {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Virtual controller, created through uinput
+struct Output {
+    device: Device,
+    // Inputs in the layout.
+    codes: Vec<Code>,
+    // Whether d-pad events are sent on the first hat (like `xpad` does).
+    dpad_hat: bool,
+    // Current value of each hat axis (ABS_HAT0X to ABS_HAT3Y).
+    hats: [c_int; 8],
+    // Relative motion smaller than one unit that hasn't been sent yet.
+    remainders: [f64; 0x0A],
+    // Uploaded rumble effects (id, strong, weak).
+    effects: Vec<(i16, f32, f32)>,
+}
+
+impl Output {
+    fn new(name: &str, layout: &Layout) -> io::Result<Self> {
+        const NONBLOCK: c_int = 0o0004000;
+        const UINPUT: &[u8] = b"/dev/uinput\0";
+
+        let fd = unsafe { open(UINPUT.as_ptr(), 2 | NONBLOCK) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        match Self::create(fd, name, layout) {
+            Ok(codes) => Ok(Self {
+                device: Device::new(fd, Watcher::new().input()),
+                codes,
+                dpad_hat: matches!(layout, Layout::Xbox),
+                hats: [0; 8],
+                remainders: [0.0; 0x0A],
+                effects: Vec::new(),
+            }),
+            Err(error) => {
+                unsafe { close(fd) };
+                Err(error)
+            }
+        }
+    }
+
+    // Set up and create the uinput device, returning the codes in use.
+    fn create(fd: RawFd, name: &str, layout: &Layout) -> io::Result<Vec<Code>> {
+        const EVBIT: c_ulong = 0x64;
+        const KEYBIT: c_ulong = 0x65;
+        const RELBIT: c_ulong = 0x66;
+        const ABSBIT: c_ulong = 0x67;
+        const FFBIT: c_ulong = 0x6B;
+
+        let mut dev: UinputUserDev =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        for (c, byte) in dev.name.iter_mut().zip(name.bytes().take(79)) {
+            *c = byte as c_char;
+        }
+        dev.id = match layout {
+            // Identify as a wired Xbox 360 controller.
+            Layout::Xbox => InputId {
+                bustype: 0x03, /* BUS_USB */
+                vendor: 0x045E,
+                product: 0x028E,
+                version: 0x0110,
+            },
+            _ => InputId {
+                bustype: 0x06, /* BUS_VIRTUAL */
+                vendor: 0,
+                product: 0,
+                version: 1,
+            },
+        };
+        dev.ff_effects_max = 16;
+
+        let codes = layout_codes(layout)?;
+
+        // EV_SYN, and EV_FF with FF_RUMBLE
+        uinput_bit(fd, EVBIT, 0x00)?;
+        uinput_bit(fd, EVBIT, 0x15)?;
+        uinput_bit(fd, FFBIT, 0x50)?;
+        for code in codes.iter().cloned() {
+            match code {
+                Code::Key(code) => {
+                    uinput_bit(fd, EVBIT, 0x01)?;
+                    uinput_bit(fd, KEYBIT, code)?;
+                }
+                Code::Rel(code) => {
+                    uinput_bit(fd, EVBIT, 0x02)?;
+                    uinput_bit(fd, RELBIT, code)?;
+                }
+                Code::Abs(code, centered) => {
+                    uinput_bit(fd, EVBIT, 0x03)?;
+                    uinput_bit(fd, ABSBIT, code)?;
+                    let code = usize::from(code);
+                    if centered {
+                        dev.absmin[code] = -32768;
+                        dev.absmax[code] = 32767;
+                    } else {
+                        dev.absmax[code] = 255;
+                    }
+                }
+                Code::Hat(code, _) => {
+                    uinput_bit(fd, EVBIT, 0x03)?;
+                    uinput_bit(fd, ABSBIT, code)?;
+                    dev.absmin[usize::from(code)] = -1;
+                    dev.absmax[usize::from(code)] = 1;
+                }
+            }
+        }
+
+        let size = size_of::<UinputUserDev>();
+        let dev: *const _ = &dev;
+        if unsafe { write(fd, dev.cast(), size) } != size as isize {
+            return Err(io::Error::last_os_error());
+        }
+        // UI_DEV_CREATE
+        if unsafe { ioctl(fd, 0x5501, std::ptr::null_mut()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(codes)
+    }
+
+    // Handle a request to upload a force feedback effect.
+    fn upload(&mut self, request_id: c_int) {
+        let size = size_of::<UinputFfUpload>() as c_ulong;
+        let mut upload: UinputFfUpload =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        upload.request_id = request_id as u32;
+        // UI_BEGIN_FF_UPLOAD
+        if unsafe {
+            ioctl(
+                self.device.raw(),
+                0xC000_55C8 | size << 16,
+                std::ptr::addr_of_mut!(upload).cast(),
+            )
+        } == -1
+        {
+            return;
+        }
+        let effect = &upload.effect;
+        if effect.stype == 0x50 {
+            let rumble = unsafe { effect.u.rumble };
+            let strong = f32::from(rumble.strong_magnitude) / 65535.0;
+            let weak = f32::from(rumble.weak_magnitude) / 65535.0;
+            self.effects.retain(|(id, _, _)| *id != effect.id);
+            self.effects.push((effect.id, strong, weak));
+            upload.retval = 0;
+        } else {
+            // EINVAL: Only rumble is supported.
+            upload.retval = -22;
+        }
+        // UI_END_FF_UPLOAD
+        unsafe {
+            ioctl(
+                self.device.raw(),
+                0x4000_55C9 | size << 16,
+                std::ptr::addr_of_mut!(upload).cast(),
+            )
+        };
     }
 
-    /// Disable all events (when window leaves focus).
-    fn disable(&self) {
-        ENABLED.store(false, std::sync::atomic::Ordering::Relaxed);
+    // Handle a request to erase a force feedback effect.
+    fn erase(&mut self, request_id: c_int) {
+        let size = size_of::<UinputFfErase>() as c_ulong;
+        let mut erase = UinputFfErase {
+            request_id: request_id as u32,
+            retval: 0,
+            effect_id: 0,
+        };
+        // UI_BEGIN_FF_ERASE
+        if unsafe {
+            ioctl(
+                self.device.raw(),
+                0xC000_55CA | size << 16,
+                std::ptr::addr_of_mut!(erase).cast(),
+            )
+        } == -1
+        {
+            return;
+        }
+        self.effects
+            .retain(|(id, _, _)| *id as u32 != erase.effect_id);
+        // UI_END_FF_ERASE
+        unsafe {
+            ioctl(
+                self.device.raw(),
+                0x4000_55CB | size << 16,
+                std::ptr::addr_of_mut!(erase).cast(),
+            )
+        };
+    }
+}
+
+impl super::Output for Output {
+    fn send(&mut self, event: Event) -> Result<(), Error> {
+        let code = match stick_event_to_linux(dpad(event, self.dpad_hat)) {
+            Some(code) if self.codes.contains(&code) => code,
+            _ => return Ok(()),
+        };
+        let value = event.value();
+        let (ev_type, ev_code, ev_value) = match code {
+            Code::Key(code) => (0x01, code, c_int::from(value != 0.0)),
+            Code::Abs(code, true) => {
+                (0x03, code, (value.clamp(-1.0, 1.0) * 32767.0).round() as _)
+            }
+            Code::Abs(code, false) => {
+                (0x03, code, (value.clamp(0.0, 1.0) * 255.0).round() as _)
+            }
+            Code::Rel(code) => {
+                let remainder = &mut self.remainders[usize::from(code)];
+                *remainder += value;
+                let units = remainder.trunc();
+                *remainder -= units;
+                if units == 0.0 {
+                    return Ok(());
+                }
+                (0x02, code, units as _)
+            }
+            Code::Hat(code, direction) => {
+                let hat = &mut self.hats[usize::from(code - 0x10)];
+                if value != 0.0 {
+                    *hat = direction;
+                } else if *hat == direction {
+                    *hat = 0;
+                } else {
+                    return Ok(());
+                }
+                (0x03, code, *hat)
+            }
+        };
+        uinput_write(self.device.raw(), ev_type, ev_code, ev_value)
+            .map_err(Error::Output)
     }
 
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(f32, f32), Error>> {
+        loop {
+            let ev = match evdev_read(self.device.raw()) {
+                Ok(Some(ev)) => ev,
+                Ok(None) => return self.device.sleep(cx),
+                // The virtual controller can't be used anymore.
+                Err(error) => return Poll::Ready(Err(Error::Output(error))),
+            };
+            match (ev.ev_type, ev.ev_code) {
+                // EV_UINPUT, UI_FF_UPLOAD
+                (0x0101, 0x01) => self.upload(ev.ev_value),
+                // EV_UINPUT, UI_FF_ERASE
+                (0x0101, 0x02) => self.erase(ev.ev_value),
+                // EV_FF, playing (or stopping with 0) an effect
+                (0x15, id) => {
+                    let effect =
+                        self.effects.iter().find(|e| e.0 as c_ushort == id);
+                    if let Some(&(_, strong, weak)) = effect {
+                        if ev.ev_value == 0 {
+                            return Poll::Ready(Ok((0.0, 0.0)));
+                        }
+                        return Poll::Ready(Ok((strong, weak)));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        // UI_DEV_DESTROY; nothing can be done if it fails.
+        unsafe { ioctl(self.device.raw(), 0x5502, std::ptr::null_mut()) };
+        unsafe { close(self.device.stop()) };
+    }
+}
+
+struct Global;
+
+impl super::Global for Global {
     /// Create a new listener.
-    fn listener(&self, remap: Remap) -> Box<dyn super::Listener> {
-        Box::new(Listener::new(remap))
+    fn listener(
+        &self,
+        remap: Remap,
+    ) -> Result<Box<dyn super::Listener>, Error> {
+        Ok(Box::new(Listener::new(remap)?))
+    }
+
+    /// Create a new virtual controller.
+    fn output(
+        &self,
+        name: &str,
+        layout: &Layout,
+    ) -> Result<Box<dyn super::Output>, Error> {
+        Ok(Box::new(Output::new(name, layout).map_err(Error::Output)?))
     }
 }
 
 pub(super) fn global() -> Box<dyn super::Global> {
     Box::new(Global)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xbox_codes() {
+        let codes = layout_codes(&Layout::Xbox).unwrap();
+        assert_eq!(codes.len(), 21);
+        assert_eq!(codes[0], Code::Key(0x130));
+        // Triggers share their axes with nothing, and aren't centred
+        assert!(codes.contains(&Code::Abs(0x02, false)));
+        assert!(codes.contains(&Code::Abs(0x05, false)));
+        // The d-pad is the first hat, like a real Xbox controller
+        assert!(codes.contains(&Code::Hat(0x10, -1)));
+        assert!(codes.contains(&Code::Hat(0x11, 1)));
+        assert!(!codes.contains(&Code::Key(0x220)));
+    }
+
+    #[test]
+    fn joystick_codes() {
+        let codes = layout_codes(&Layout::Joystick).unwrap();
+        assert_eq!(codes[..2], [Code::Abs(0x00, true), Code::Abs(0x01, true)]);
+        assert!(codes.contains(&Code::Abs(0x06, false)));
+        for n in 0x126..=0x12F {
+            assert!(codes.contains(&Code::Key(n)));
+        }
+    }
+
+    #[test]
+    fn custom_codes() {
+        let layout = Layout::Custom(vec![
+            Event::JoyZ(0.0),
+            Event::TriggerL(0.0),
+            Event::Up(false),
+            Event::MouseX(0.0),
+        ]);
+        // Only the first event gets ABS_Z, and the d-pad stays buttons
+        assert_eq!(
+            layout_codes(&layout).unwrap(),
+            [Code::Abs(0x02, true), Code::Key(0x220), Code::Rel(0x00)],
+        );
+    }
+
+    #[test]
+    fn custom_validation() {
+        let error = layout_codes(&Layout::Custom(Vec::new())).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let layout = Layout::Custom(vec![
+            Event::ActionA(false),
+            Event::Number(51, false),
+        ]);
+        let error = layout_codes(&layout).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
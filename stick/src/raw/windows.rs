@@ -19,7 +19,7 @@ use winapi::{
     },
 };
 
-use crate::{Event, Remap};
+use crate::{Capabilities, Effect, Error, Event, Remap};
 
 type XInputEnableFunc = unsafe extern "system" fn(BOOL);
 type XInputGetStateFunc =
@@ -789,6 +789,42 @@ impl super::Controller for Controller {
     fn name(&self) -> &str {
         "XInput Controller"
     }
+
+    /// XInput controllers all have the same layout.
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = Capabilities::default();
+        for event in [
+            Event::MenuR(false),
+            Event::MenuL(false),
+            Event::ActionA(false),
+            Event::ActionB(false),
+            Event::ActionH(false),
+            Event::ActionV(false),
+            Event::BumperL(false),
+            Event::BumperR(false),
+            Event::Joy(false),
+            Event::Cam(false),
+            Event::Up(false),
+            Event::Down(false),
+            Event::Left(false),
+            Event::Right(false),
+        ] {
+            caps.input(event, None);
+        }
+        let stick = Some((i16::MIN.into(), i16::MAX.into()));
+        for event in [
+            Event::JoyX(0.0),
+            Event::JoyY(0.0),
+            Event::CamX(0.0),
+            Event::CamY(0.0),
+        ] {
+            caps.input(event, stick);
+        }
+        caps.input(Event::TriggerL(0.0), Some((0, 255)));
+        caps.input(Event::TriggerR(0.0), Some((0, 255)));
+        caps.effect(Effect::Rumble);
+        caps
+    }
 }
 
 pub(crate) struct Listener {
@@ -857,8 +893,11 @@ impl super::Global for Global {
     }
 
     /// Create a new listener.
-    fn listener(&self, remap: Remap) -> Box<dyn super::Listener> {
-        Box::new(Listener::new(remap, self.xinput.clone()))
+    fn listener(
+        &self,
+        remap: Remap,
+    ) -> Result<Box<dyn super::Listener>, Error> {
+        Ok(Box::new(Listener::new(remap, self.xinput.clone())))
     }
 }
 
@@ -0,0 +1,281 @@
+//! The mapping database format (also compiled into static tables by
+//! `build.rs`).
+//!
+//! Each line is a controller: its 16 hexadecimal digit ID, its name, a tab,
+//! its type character, and `;` separated remappings.  A remapping is an input,
+//! the two hexadecimal digit code of the output event, and tweaks.
+//!
+//! Digits of the ID can be `*` to match any digit (such as any version), and
+//! lines can end with a tab and a priority, then a tab and a pattern the
+//! controller's name must match (where `*` matches any text, ignoring ASCII
+//! case).
+
+/// A remapping from an input to an output event.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Map {
+    pub(crate) deadzone: f64,
+    pub(crate) scale: f64,
+    pub(crate) max: i32,
+    pub(crate) min: i32,
+    // Flip the input axis.
+    pub(crate) invert: bool,
+    // Use only the positive (1) or negative (-1) half of the input axis.
+    pub(crate) half: i8,
+    // Drive only the positive (1) or negative (-1) half of the output axis.
+    pub(crate) out_half: i8,
+    pub(crate) out: u8,
+}
+
+impl Map {
+    /// A remapping to `out`, without tweaks.
+    pub(crate) const fn new(out: u8) -> Self {
+        Self {
+            deadzone: f64::NAN,
+            scale: f64::NAN,
+            max: 0,
+            min: 0,
+            invert: false,
+            half: 0,
+            out_half: 0,
+            out,
+        }
+    }
+}
+
+/// An input, by how SDL numbers it on the device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SdlInput {
+    Button(u8),
+    Axis(u8),
+    // Hat and direction (1 is up, 2 right, 4 down and 8 left).
+    Hat(u8, u8),
+}
+
+/// The input of a remapping.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Input {
+    // Event code
+    Code(u8),
+    // Platform-specific input stick doesn't recognize, by kind and code
+    Raw(u16, u16),
+    // Input numbered like SDL, resolved when the controller is connected
+    Sdl(SdlInput),
+}
+
+/// A controller from a built-in database.
+#[derive(Debug)]
+pub(crate) struct Builtin {
+    pub(crate) id: u64,
+    // Which bits of the ID must match.
+    pub(crate) mask: u64,
+    pub(crate) priority: i8,
+    // Pattern the name must match.
+    pub(crate) pattern: Option<&'static str>,
+    pub(crate) type_: char,
+    pub(crate) maps: &'static [(Input, Map)],
+}
+
+/// A controller, parsed from a database line.
+pub(crate) struct Line<'a> {
+    pub(crate) id: u64,
+    pub(crate) mask: u64,
+    pub(crate) priority: i8,
+    pub(crate) pattern: Option<&'a str>,
+    pub(crate) type_: char,
+    pub(crate) maps: Vec<(Input, Map)>,
+}
+
+// Parse a remapping.
+fn parse_map(event: &str) -> Option<(Input, Map)> {
+    let hex = |range| u16::from_str_radix(event.get(range)?, 16).ok();
+    let (input, mut cursor) = match event.get(..1)? {
+        // Raw inputs are `R`, followed by the kind and code
+        "R" => (Input::Raw(hex(1..5)?, hex(5..9)?), 9),
+        // SDL inputs are `S`, followed by `B` (button), `A` (axis) or `H`
+        // (hat) and the SDL index (and direction for hats)
+        "S" => {
+            let sdl = match event.get(1..2)? {
+                "B" => SdlInput::Button(hex(2..4)? as u8),
+                "A" => SdlInput::Axis(hex(2..4)? as u8),
+                "H" => SdlInput::Hat(hex(2..3)? as u8, hex(3..4)? as u8),
+                _ => return None,
+            };
+            (Input::Sdl(sdl), 4)
+        }
+        _ => (Input::Code(hex(0..2)? as u8), 2),
+    };
+    let out = hex(cursor..cursor + 2)? as u8;
+    cursor += 2;
+
+    // Tweaks
+    let mut map = Map::new(out);
+    while let Some(tweak) = event.get(cursor..)?.chars().next() {
+        let rest = event.get(cursor + 1..)?;
+        let end = rest.find(char::is_lowercase).unwrap_or(rest.len());
+        let value = &rest[..end];
+        match tweak {
+            'd' => map.deadzone = value.parse::<f64>().ok()?,
+            's' => map.scale = value.parse::<f64>().ok()?.recip(),
+            'a' => map.max = value.parse::<i32>().ok()?,
+            'i' => map.min = value.parse::<i32>().ok()?,
+            'v' if value.is_empty() => map.invert = true,
+            'h' => map.half = value.parse::<i8>().ok()?.signum(),
+            'o' => map.out_half = value.parse::<i8>().ok()?.signum(),
+            _ => return None,
+        }
+        cursor += end + 1;
+    }
+
+    Some((input, map))
+}
+
+// Parse an ID, returning it and which bits must match.
+fn parse_id(id: &str) -> Option<(u64, u64)> {
+    let (mut value, mut mask) = (0, 0);
+    for digit in id.chars() {
+        value <<= 4;
+        mask <<= 4;
+        if digit != '*' {
+            value |= u64::from(digit.to_digit(16)?);
+            mask |= 0xF;
+        }
+    }
+    Some((value, mask))
+}
+
+/// Whether a controller's name matches a name pattern (a glob, where `*`
+/// matches any text), ignoring ASCII case.
+pub(crate) fn name_matches(pattern: &str, name: &str) -> bool {
+    let (pattern, name) =
+        (pattern.to_ascii_lowercase(), name.to_ascii_lowercase());
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    // Text between `*`s matches where it's first found.
+    for part in parts {
+        let Some(i) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[i + part.len()..];
+    }
+    rest.ends_with(last)
+}
+
+/// Parse a database line, `None` if it's malformed.
+pub(crate) fn parse(line: &str) -> Option<Line<'_>> {
+    let mut fields = line.splitn(4, '\t');
+    // The name is only for people reading the database.
+    let (id, _name) = fields.next()?.split_at_checked(16)?;
+    let (id, mask) = parse_id(id)?;
+    let maps = fields.next()?;
+    let type_ = maps.chars().next()?;
+    let maps = match maps.get(1..)? {
+        "" => Vec::new(),
+        maps => maps.split(';').map(parse_map).collect::<Option<_>>()?,
+    };
+    let priority = match fields.next() {
+        Some(priority) => priority.parse().ok()?,
+        None => 0,
+    };
+    let pattern = fields.next();
+
+    Some(Line {
+        id,
+        mask,
+        priority,
+        pattern,
+        type_,
+        maps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line() {
+        let line = parse(
+            "03005E04****1001Pad\tx0203;2020d0.1s2i-5a5;R0003001822;SA0123v;\
+                SH0411;SB0524o-1\t2\t*pad",
+        )
+        .unwrap();
+        assert_eq!(line.id, 0x03005E0400001001);
+        assert_eq!(line.mask, 0xFFFFFFFF0000FFFF);
+        assert_eq!(line.type_, 'x');
+        assert_eq!(line.priority, 2);
+        assert_eq!(line.pattern, Some("*pad"));
+
+        let inputs: Vec<Input> = line.maps.iter().map(|(i, _)| *i).collect();
+        assert_eq!(
+            inputs,
+            [
+                Input::Code(0x02),
+                Input::Code(0x20),
+                Input::Raw(0x0003, 0x0018),
+                Input::Sdl(SdlInput::Axis(1)),
+                Input::Sdl(SdlInput::Hat(0, 4)),
+                Input::Sdl(SdlInput::Button(5)),
+            ]
+        );
+        let outs: Vec<u8> = line.maps.iter().map(|(_, m)| m.out).collect();
+        assert_eq!(outs, [0x03, 0x20, 0x22, 0x23, 0x11, 0x24]);
+
+        let (_, tweaked) = line.maps[1];
+        assert_eq!(tweaked.deadzone, 0.1);
+        assert_eq!(tweaked.scale, 0.5);
+        assert_eq!((tweaked.min, tweaked.max), (-5, 5));
+        assert!(line.maps[3].1.invert);
+        assert_eq!(line.maps[5].1.out_half, -1);
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let line = parse("0300AAAABBBB0100Pad\tg0203").unwrap();
+        assert_eq!(line.mask, u64::MAX);
+        assert_eq!(line.priority, 0);
+        assert_eq!(line.pattern, None);
+        assert!(line.maps[0].1.deadzone.is_nan());
+
+        // No remappings
+        assert!(parse("0300AAAABBBB0100Pad\tg").unwrap().maps.is_empty());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for line in [
+            "",
+            "0300AAAA",
+            "0300AAAABBBB0100Pad",
+            "0300AAAABBBB0100Pad\t",
+            "0300GGGGBBBB0100Pad\tg0203",
+            "0300AAAABBBB0100Pad\tg02",
+            "0300AAAABBBB0100Pad\tg0203;",
+            "0300AAAABBBB0100Pad\tg0203z",
+            "0300AAAABBBB0100Pad\tg0203dx",
+            "0300AAAABBBB0100Pad\tg0203\thigh",
+            "0300AAAABBBB0100Pad\tgSX0203",
+        ] {
+            assert!(parse(line).is_none(), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn name_patterns() {
+        assert!(name_matches("Pad", "pad"));
+        assert!(!name_matches("Pad", "Gamepad"));
+        assert!(name_matches("*pad", "Gamepad"));
+        assert!(name_matches("game*", "Gamepad"));
+        assert!(name_matches("*WIRELESS*", "Sony Wireless Controller"));
+        assert!(name_matches("s*y*controller", "Sony Wireless Controller"));
+        assert!(!name_matches("s*x*controller", "Sony Wireless Controller"));
+        assert!(!name_matches("a*a", "a"));
+        assert!(name_matches("*", ""));
+    }
+}
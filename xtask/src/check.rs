@@ -0,0 +1,311 @@
+use std::{collections::HashMap, path::Path};
+
+use stick::{Event, Remap};
+use toml::value::{Table, Value};
+
+use stick_mapping as mapping;
+
+const LINUX: &str = "./sdb/linux/";
+const LINUX_SDB: &str = "./stick/remap_linux.sdb";
+const LINUX_SDL: &str = "./stick/sdlgc_linux.sdb";
+
+const TYPES: &[&str] =
+    &["xbox", "playstation", "nintendo", "gamepad", "flight"];
+const TWEAKS: &[&str] = &["max", "min", "scale", "deadzone", "invert", "half"];
+
+/// Problems found in the database.
+#[derive(Default)]
+struct Report {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn error(&mut self, file: &str, key: Option<&str>, message: &str) {
+        self.errors += 1;
+        match key {
+            Some(key) => eprintln!("error: {}: `{}`: {}", file, key, message),
+            None => eprintln!("error: {}: {}", file, message),
+        }
+    }
+
+    fn warning(&mut self, file: &str, message: &str) {
+        self.warnings += 1;
+        eprintln!("warning: {}: {}", file, message);
+    }
+}
+
+// Check an input key, returning whether it's a button.
+fn check_input(report: &mut Report, file: &str, key: &str) -> Option<bool> {
+    if let Ok(number) = key.parse::<u8>() {
+        if !(1..=0x7F).contains(&number) {
+            report.error(file, Some(key), "numbered buttons are 1 to 127");
+        }
+        Some(true)
+    } else if let Some(raw) = key.strip_prefix("Raw:") {
+        let parsed = raw.split_once(':').and_then(|(kind, code)| {
+            let kind = u16::from_str_radix(kind, 16).ok()?;
+            u16::from_str_radix(code, 16).ok()?;
+            Some(kind)
+        });
+        match parsed {
+            // EV_KEY
+            Some(kind) => Some(kind == 0x01),
+            None => {
+                report.error(file, Some(key), "expected `Raw:KKKK:CCCC`");
+                None
+            }
+        }
+    } else if let Some(sdl) = key.strip_prefix("Sdl:") {
+        if mapping::sdl_to_hex(sdl).is_none() {
+            report.error(
+                file,
+                Some(key),
+                "expected `Sdl:bN`, `Sdl:aN` or `Sdl:hN.D`",
+            );
+            return None;
+        }
+        Some(!sdl.starts_with('a'))
+    } else if let Some(code) = mapping::event_code(key) {
+        Some(!Event::from_code(code, 0.0).is_some_and(Event::is_axis))
+    } else {
+        report.error(file, Some(key), "unknown input");
+        None
+    }
+}
+
+// Check a mapping, returning the event it maps to (and which half of it).
+fn check_output(
+    report: &mut Report,
+    file: &str,
+    key: &str,
+    value: &Value,
+    button: Option<bool>,
+) -> Option<String> {
+    let event = match value {
+        Value::String(event) => Some(event.as_str()),
+        Value::Table(table) => check_tweaks(report, file, key, table, button),
+        _ => {
+            report.error(file, Some(key), "expected an event or a table");
+            return None;
+        }
+    };
+    let event = event.unwrap_or("None");
+    if mapping::event_code(event).is_none() {
+        report.error(file, Some(key), &format!("unknown event `{}`", event));
+        return None;
+    }
+    match value.get("output_half").and_then(Value::as_str) {
+        Some(half) => Some(format!("{} ({} half)", event, half)),
+        None => Some(event.to_string()),
+    }
+}
+
+// Check a table of tweaks, returning the event it maps to.
+fn check_tweaks<'a>(
+    report: &mut Report,
+    file: &str,
+    key: &str,
+    table: &'a Table,
+    button: Option<bool>,
+) -> Option<&'a str> {
+    for (tweak, value) in table {
+        let valid = match tweak.as_str() {
+            "event" => value.is_str(),
+            "max" | "min" => value.is_integer(),
+            "scale" | "deadzone" => value.is_float(),
+            "invert" => value.is_bool(),
+            "half" | "output_half" => {
+                matches!(value.as_str(), Some("+") | Some("-"))
+            }
+            _ => {
+                report.error(file, Some(key), &format!("unknown `{}`", tweak));
+                continue;
+            }
+        };
+        if !valid {
+            report.error(file, Some(key), &format!("bad type for `{}`", tweak));
+        }
+    }
+    if button == Some(true) && TWEAKS.iter().any(|t| table.contains_key(*t)) {
+        report.error(file, Some(key), "tweaks have no effect on buttons");
+    }
+    table.get("event").and_then(Value::as_str)
+}
+
+// Check the `[match]` table, for which controllers a mapping applies to.
+fn check_match(report: &mut Report, file: &str, matches: &Value) {
+    let Some(matches) = matches.as_table() else {
+        return report.error(file, Some("match"), "expected a table");
+    };
+    for (key, value) in matches {
+        let valid = match key.as_str() {
+            "ignore" => value.as_array().is_some_and(|parts| {
+                parts.iter().all(|part| {
+                    matches!(
+                        part.as_str(),
+                        Some("bus" | "vendor" | "product" | "version")
+                    )
+                })
+            }),
+            "name" => value.as_str().is_some_and(|pattern| {
+                !pattern.is_empty() && !pattern.contains(char::is_control)
+            }),
+            "priority" => {
+                value.as_integer().is_some_and(|p| i8::try_from(p).is_ok())
+            }
+            _ => {
+                report.error(file, Some(key), "unknown match");
+                continue;
+            }
+        };
+        if !valid {
+            let message = match key.as_str() {
+                "ignore" => {
+                    "expected a list of `bus`, `vendor`, `product` \
+                    and `version`"
+                }
+                "name" => {
+                    "expected a name pattern (where `*` matches any text)"
+                }
+                _ => "expected an integer from -128 to 127",
+            };
+            report.error(file, Some(key), message);
+        }
+    }
+}
+
+// Check one mapping file.
+fn check_file(
+    report: &mut Report,
+    path: &Path,
+    sdl: &[&str],
+    sdb: &mut Vec<String>,
+) {
+    let file = path.display().to_string();
+
+    // File name is the controller ID.
+    let id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let hex = id.len() == 16 && id.chars().all(|c| c.is_ascii_hexdigit());
+    if !hex || path.extension() != Some("toml".as_ref()) {
+        report.error(&file, None, "file name isn't a 16 hex digit ID + .toml");
+    } else if sdl.contains(&id.to_uppercase().as_str()) {
+        report.warning(&file, "ID also in the SDL database (this overrides)");
+    }
+
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => return report.error(&file, None, &e.to_string()),
+    };
+    let table = match toml::from_str::<Table>(&text) {
+        Ok(table) => table,
+        Err(e) => return report.error(&file, None, &e.to_string()),
+    };
+    match table.get("name") {
+        Some(Value::String(_)) => {}
+        _ => report.error(&file, Some("name"), "expected a string"),
+    }
+    match table.get("type").and_then(Value::as_str) {
+        Some(kind) if TYPES.contains(&kind) => {}
+        _ => report.error(
+            &file,
+            Some("type"),
+            &format!("expected one of {}", TYPES.join(", ")),
+        ),
+    }
+    if let Some(matches) = table.get("match") {
+        check_match(report, &file, matches);
+    }
+    let remap = match table.get("remap") {
+        Some(Value::Table(remap)) => remap,
+        _ => return report.error(&file, Some("remap"), "expected a table"),
+    };
+
+    // Inputs, by the event they're mapped to.
+    let mut targets = HashMap::<String, Vec<&str>>::new();
+    for (key, value) in remap {
+        let button = check_input(report, &file, key);
+        // An input can be mapped to a list of events.
+        let values = match value {
+            Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            if let Some(event) = check_output(report, &file, key, value, button)
+            {
+                targets.entry(event).or_default().push(key);
+            }
+        }
+    }
+    let mut targets: Vec<_> = targets.into_iter().collect();
+    targets.sort();
+    for (event, mut keys) in targets {
+        if event != "None" && keys.len() > 1 {
+            keys.sort_unstable();
+            let keys = keys.join("`, `");
+            report
+                .warning(&file, &format!("`{}` all map to `{}`", keys, event));
+        }
+    }
+
+    // Generated database line must load.
+    if hex {
+        match mapping::to_sdb(&id.to_uppercase(), &text) {
+            Ok(line) if Remap::new().load(&line).is_some() => sdb.push(line),
+            Ok(line) => report.error(&file, None, &format!("bad: {}", line)),
+            Err(_) => {} // Already reported
+        }
+    }
+}
+
+pub(super) fn main() {
+    let mut report = Report::default();
+
+    println!("Checking Linux TOML Controller Mappings…");
+    let sdl = std::fs::read_to_string(LINUX_SDL).unwrap_or_default();
+    let sdl: Vec<&str> = sdl.lines().filter_map(|l| l.get(..16)).collect();
+    let mut paths: Vec<_> = std::fs::read_dir(LINUX)
+        .expect("Missing database")
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    let mut sdb = Vec::new();
+    for path in &paths {
+        check_file(&mut report, path, &sdl, &mut sdb);
+    }
+
+    // The embedded databases must load, and be up to date.
+    for file in [LINUX_SDB, LINUX_SDL] {
+        let text = std::fs::read_to_string(file).unwrap_or_default();
+        if Remap::new().load(&text).is_none() {
+            report.error(file, None, "doesn't load with `Remap::load()`");
+        }
+    }
+    if report.errors == 0 {
+        let text = std::fs::read_to_string(LINUX_SDB).unwrap_or_default();
+        let mut lines: Vec<&str> = text.lines().collect();
+        lines.sort_unstable();
+        sdb.sort_unstable();
+        if lines != sdb {
+            report.error(
+                LINUX_SDB,
+                None,
+                "out of date (run `cargo xtask sdb`)",
+            );
+        }
+    }
+
+    println!(
+        "{} files checked: {} errors, {} warnings",
+        paths.len(),
+        report.errors,
+        report.warnings,
+    );
+    if report.errors != 0 {
+        std::process::exit(1);
+    }
+}
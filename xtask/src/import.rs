@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use crate::sdb::{sdl_id, sdl_input, sdl_output, SDL};
+
+const LINUX: &str = "./sdb/linux/";
+
+fn print_help() {
+    eprintln!("Usage: cargo xtask import-sdl <GUID | MAPPING>");
+    eprintln!();
+    eprintln!(
+        "Write sdb/linux/<ID>.toml from the Linux SDL mapping for GUID in"
+    );
+    eprintln!("gcdb/gamecontrollerdb.txt, or from a pasted SDL mapping line.");
+}
+
+// Find the Linux mapping line for `guid` in the SDL database.
+fn find(guid: &str) -> Result<String, String> {
+    let text = std::fs::read_to_string(SDL).map_err(|e| {
+        format!("{}: {} (run `git submodule update --init`)", SDL, e)
+    })?;
+    text.lines()
+        .find(|line| {
+            line.get(..32).is_some_and(|g| g.eq_ignore_ascii_case(guid))
+                && line.contains("platform:Linux")
+        })
+        .map(str::to_string)
+        .ok_or_else(|| format!("No Linux mapping for {} in {}", guid, SDL))
+}
+
+// Guess the controller type from the vendor, then the name.
+fn kind(id: &str, name: &str) -> &'static str {
+    let vendor = u16::from_str_radix(&id[4..8], 16).unwrap().swap_bytes();
+    let name = name.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| name.contains(w));
+    match vendor {
+        0x045E => "xbox",
+        0x054C => "playstation",
+        0x057E => "nintendo",
+        _ if has(&["xbox", "x-box", "xinput"]) => "xbox",
+        _ if has(&["playstation", "ps3", "ps4", "ps5", "dualshock"]) => {
+            "playstation"
+        }
+        _ if has(&["nintendo", "switch", "joy-con", "wii"]) => "nintendo",
+        _ => "gamepad",
+    }
+}
+
+// Quote a TOML key, if needed.
+fn key(input: &str) -> String {
+    if input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        input.to_string()
+    } else {
+        toml::Value::String(input.to_string()).to_string()
+    }
+}
+
+// Convert an SDL mapping line to a TOML mapping file, returning the ID too.
+fn convert(line: &str) -> Result<(String, String), String> {
+    let mut fields = line.trim().split(',');
+    let guid = fields.next().unwrap_or_default();
+    let id = sdl_id(guid)
+        .ok_or_else(|| format!("{} isn't a Linux device GUID", guid))?;
+    let name = fields.next().ok_or("No name")?;
+
+    // Inputs, and the events (with tweaks) they're mapped to.
+    let mut remap = Vec::<(String, Vec<String>)>::new();
+    let mut skipped = Vec::new();
+    for mapping in fields {
+        let (js_out, js_in) = match mapping.split_once(':') {
+            Some(("platform" | "crc" | "hint", _)) | None => continue,
+            Some(pair) => pair,
+        };
+        let (input, (event, out_half)) =
+            match (sdl_input(js_in), sdl_output(js_out)) {
+                (Ok(input), Ok(output)) => (input, output),
+                _ => {
+                    skipped.push(format!("{} (unsupported)", mapping));
+                    continue;
+                }
+            };
+        let sign = |half: i8| if half < 0 { "-" } else { "+" };
+        let mut tweaks = Vec::new();
+        if input.invert {
+            tweaks.push("invert = true".to_string());
+        }
+        if input.half != 0 {
+            tweaks.push(format!("half = \"{}\"", sign(input.half)));
+        }
+        if out_half != 0 {
+            tweaks.push(format!("output_half = \"{}\"", sign(out_half)));
+        }
+        let value = if tweaks.is_empty() {
+            format!("\"{}\"", event)
+        } else {
+            format!("{{ event = \"{}\", {} }}", event, tweaks.join(", "))
+        };
+        let input = format!("Sdl:{}", input.input);
+        match remap.iter_mut().find(|(used, _)| *used == input) {
+            Some((_, values)) => values.push(value),
+            None => remap.push((input, vec![value])),
+        }
+    }
+    if remap.is_empty() {
+        return Err(format!("No mappings could be imported for {}", name));
+    }
+
+    let mut out = String::new();
+    out.push_str(&format!("name = {}\n", toml::Value::from(name)));
+    out.push_str(&format!("type = \"{}\"\n", kind(&id, name)));
+    out.push_str("\n[remap]\n");
+    for (input, values) in remap {
+        let value = match values.as_slice() {
+            [value] => value.clone(),
+            values => format!("[{}]", values.join(", ")),
+        };
+        out.push_str(&format!("{} = {}\n", key(&input), value));
+    }
+    if !skipped.is_empty() {
+        out.push_str("# Skipped SDL mappings:\n");
+        for mapping in skipped {
+            out.push_str(&format!("# {}\n", mapping));
+        }
+    }
+    Ok((id, out))
+}
+
+fn import(arg: &str) -> Result<(), String> {
+    // A pasted mapping line has commas, a GUID doesn't.
+    let line = if arg.contains(',') {
+        arg.to_string()
+    } else {
+        find(arg)?
+    };
+    let (id, toml) = convert(&line)?;
+    let path = Path::new(LINUX).join(format!("{}.toml", id));
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    std::fs::write(&path, toml).map_err(|e| e.to_string())?;
+    println!("Wrote {}", path.display());
+    println!(
+        "Review it, then run `cargo xtask sdb-check` and `cargo xtask sdb`"
+    );
+    Ok(())
+}
+
+pub(super) fn main(arg: Option<&str>) {
+    let arg = match arg {
+        Some(arg) if arg != "--help" => arg,
+        _ => return print_help(),
+    };
+    if let Err(error) = import(arg) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -1,12 +1,16 @@
 use std::env;
 
+mod check;
+mod import;
 mod sdb;
 
 fn print_help() {
     eprintln!("Tasks:");
     eprintln!();
     eprintln!("--help          Print this help text");
+    eprintln!("import-sdl      Add a TOML mapping from the SDL database");
     eprintln!("sdb             Generate stick & gcdb bytecode databases");
+    eprintln!("sdb-check       Check the stick database for problems");
 }
 
 fn print_unknown(x: &str) {
@@ -19,10 +23,20 @@ fn sdb() {
     sdb::main()
 }
 
+fn sdb_check() {
+    check::main()
+}
+
+fn import_sdl(arg: Option<&str>) {
+    import::main(arg)
+}
+
 fn main() {
     let task = env::args().nth(1);
     match task.as_deref() {
         Some("sdb") => sdb(),
+        Some("sdb-check") => sdb_check(),
+        Some("import-sdl") => import_sdl(env::args().nth(2).as_deref()),
         None | Some("--help") => print_help(),
         Some(x) => print_unknown(x),
     }
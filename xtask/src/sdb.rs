@@ -1,6 +1,4 @@
-use std::{collections::HashMap, fmt::Write};
-
-use serde_derive::Deserialize;
+use stick_mapping as mapping;
 
 const LINUX: &str = "./sdb/linux/";
 const _MACOS: &str = "./sdb/macos/";
@@ -8,115 +6,136 @@ const _WINDOWS: &str = "./sdb/windows/";
 const _WEB: &str = "./sdb/web/";
 const _USB: &str = "./sdb/usb/";
 
-const SDL: &str = "./gcdb/gamecontrollerdb.txt";
+pub(crate) const SDL: &str = "./gcdb/gamecontrollerdb.txt";
+
+fn hex(name: &str) -> String {
+    mapping::event_to_hex(name).unwrap_or_else(|| panic!("Unknown: {}", name))
+}
+
+// Get the controller ID from an SDL GUID, `None` for emulated joysticks.
+pub(crate) fn sdl_id(guid: &str) -> Option<String> {
+    let guid = guid.get(0..32)?;
+    if guid.get(2..8) != Some("000000")
+        || guid.get(12..16) != Some("0000")
+        || guid.get(20..24) != Some("0000")
+        || guid.get(28..32) != Some("0000")
+    {
+        return None;
+    }
+    // Bus, vendor, product and version.
+    let mut id = String::new();
+    for range in [0..4, 8..12, 16..20, 24..28] {
+        id.push_str(&guid[range].to_uppercase());
+    }
+    Some(id)
+}
+
+/// An SDL joystick input, as a database input.
+pub(crate) struct SdlInput {
+    /// The SDL input, without the half or inversion (such as `a2`).
+    pub(crate) input: String,
+    /// Hexadecimal database input.
+    pub(crate) hex: String,
+    /// Only the positive (1) or negative (-1) half of the axis is used.
+    pub(crate) half: i8,
+    /// The axis is upside-down.
+    pub(crate) invert: bool,
+}
+
+// Split the `+` or `-` half prefix off an SDL input or output.
+fn half(sdl: &str) -> (i8, &str) {
+    if let Some(sdl) = sdl.strip_prefix('+') {
+        (1, sdl)
+    } else if let Some(sdl) = sdl.strip_prefix('-') {
+        (-1, sdl)
+    } else {
+        (0, sdl)
+    }
+}
 
-#[derive(Deserialize)]
-struct Map {
-    name: String,
-    r#type: String,
-    remap: HashMap<String, toml::value::Value>,
+// Get the database input for an SDL joystick input (`b3`, `a2`, `+a2`, `a2~`
+// or `h0.4`).
+pub(crate) fn sdl_input(js_in: &str) -> Result<SdlInput, String> {
+    let (half, input) = half(js_in);
+    let (input, invert) = match input.strip_suffix('~') {
+        Some(input) => (input, true),
+        None => (input, false),
+    };
+    let hex = mapping::sdl_to_hex(input)
+        .ok_or_else(|| format!("Unknown input {}", js_in))?;
+    if (half != 0 || invert) && !input.starts_with('a') {
+        return Err(format!("Only axes have halves: {}", js_in));
+    }
+    Ok(SdlInput {
+        input: input.to_string(),
+        hex,
+        half,
+        invert,
+    })
 }
 
-fn name_to_hex(name: &str) -> &str {
-    match name {
-        "None" => "00",
-        "Exit" => "01",
-        "ActionA" => "02",
-        "ActionB" => "03",
-        "ActionC" => "04",
-        "ActionH" => "05",
-        "ActionV" => "06",
-        "ActionD" => "07",
-        "MenuL" => "08",
-        "MenuR" => "09",
-        "Joy" => "0A",
-        "Cam" => "0B",
-        "BumperL" => "0C",
-        "BumperR" => "0D",
-        "TriggerL" => "0E",
-        "TriggerR" => "0F",
-        "Up" => "10",
-        "Down" => "11",
-        "Left" => "12",
-        "Right" => "13",
-        "HatUp" => "14",
-        "HatDown" => "15",
-        "HatLeft" => "16",
-        "HatRight" => "17",
-        "MicUp" => "18",
-        "MicDown" => "19",
-        "MicLeft" => "1A",
-        "MicRight" => "1B",
-        "PovUp" => "1C",
-        "PovDown" => "1D",
-        "PovLeft" => "1E",
-        "PovRight" => "1F",
-        "JoyX" => "20",
-        "JoyY" => "21",
-        "JoyZ" => "22",
-        "CamX" => "23",
-        "CamY" => "24",
-        "CamZ" => "25",
-        "Slew" => "26",
-        "Throttle" => "27",
-        "ThrottleL" => "28",
-        "ThrottleR" => "29",
-        "Volume" => "2A",
-        "Wheel" => "2B",
-        "Rudder" => "2C",
-        "Gas" => "2D",
-        "Brake" => "2E",
-        "MicPush" => "2F",
-        "Trigger" => "30",
-        "Bumper" => "31",
-        "ActionL" => "32",
-        "ActionM" => "33",
-        "ActionR" => "34",
-        "Pinky" => "35",
-        "PinkyForward" => "36",
-        "PinkyBackward" => "37",
-        "FlapsUp" => "38",
-        "FlapsDown" => "39",
-        "BoatForward" => "3A",
-        "BoatBackward" => "3B",
-        "AutopilotPath" => "3C",
-        "AutopilotAlt" => "3D",
-        "EngineMotorL" => "3E",
-        "EngineMotorR" => "3F",
-        "EngineFuelFlowL" => "40",
-        "EngineFuelFlowR" => "41",
-        "EngineIgnitionL" => "42",
-        "EngineIgnitionR" => "43",
-        "SpeedbrakeBackward" => "44",
-        "SpeedbrakeForward" => "45",
-        "ChinaBackward" => "46",
-        "ChinaForward" => "47",
-        "Apu" => "48",
-        "RadarAltimeter" => "49",
-        "LandingGearSilence" => "4A",
-        "Eac" => "4B",
-        "AutopilotToggle" => "4C",
-        "ThrottleButton" => "4D",
-        "MouseX" => "4E",
-        "MouseY" => "4F",
-        "Mouse" => "50",
-        "PaddleLeft" => "51",
-        "PaddleRight" => "52",
-        "PinkyLeft" => "53",
-        "PinkyRight" => "54",
-        "Context" => "55",
-        "Dpi" => "56",
-        "ScrollX" => "57",
-        "ScrollY" => "58",
-        "Scroll" => "59",
-        "TrimUp" => "5A",
-        "TrimDown" => "5B",
-        "TrimLeft" => "5C",
-        "TrimRight" => "5D",
-        "ActionWheelX" => "5E",
-        "ActionWheelY" => "5F",
-        _unknown => panic!("Unknown: {}", _unknown),
+// Get the stick event for an SDL gamepad output, and which half of the axis
+// it drives (`+leftx` or `-leftx`).
+pub(crate) fn sdl_output(js_out: &str) -> Result<(&'static str, i8), String> {
+    let (half, output) = half(js_out);
+    let event = match output {
+        "a" => "ActionA",
+        "b" => "ActionB",
+        "x" => "ActionH",
+        "y" => "ActionV",
+        "back" => "MenuL",
+        "start" => "MenuR",
+        "guide" => "Exit",
+        "leftshoulder" => "BumperL",
+        "lefttrigger" => "TriggerL",
+        "leftx" => "JoyX",
+        "lefty" => "JoyY",
+        "rightx" => "CamX",
+        "righty" => "CamY",
+        "rightshoulder" => "BumperR",
+        "righttrigger" => "TriggerR",
+        "leftstick" => "Joy",
+        "rightstick" => "Cam",
+        "dpleft" => "Left",
+        "dpright" => "Right",
+        "dpup" => "Up",
+        "dpdown" => "Down",
+        "misc1" => "1",
+        "misc2" => "2",
+        "misc3" => "3",
+        "misc4" => "4",
+        "misc5" => "5",
+        "misc6" => "6",
+        "touchpad" => "Touchpad",
+        "paddle1" => "PaddleLeft",
+        "paddle2" => "PaddleRight",
+        "paddle3" => "PinkyLeft",
+        "paddle4" => "PinkyRight",
+        _out => return Err(format!("Unknown output {}", js_out)),
+    };
+    let axis = matches!(output, "leftx" | "lefty" | "rightx" | "righty");
+    if half != 0 && !axis {
+        return Err(format!("Only axes have halves: {}", js_out));
     }
+    Ok((event, half))
+}
+
+// Get the database mapping for an SDL mapping (`output:input`).
+fn sdl_mapping(js_out: &str, js_in: &str) -> Result<String, String> {
+    let input = sdl_input(js_in)?;
+    let (event, out_half) = sdl_output(js_out)?;
+    let mut out = input.hex;
+    out.push_str(&hex(event));
+    if input.invert {
+        out.push('v');
+    }
+    if input.half != 0 {
+        out.push_str(&format!("h{}", input.half));
+    }
+    if out_half != 0 {
+        out.push_str(&format!("o{}", out_half));
+    }
+    Ok(out)
 }
 
 pub(super) fn main() {
@@ -129,77 +148,8 @@ pub(super) fn main() {
     {
         let path = file.path();
         let file = std::fs::read_to_string(&path).expect("Open file failed");
-        let file: Map = toml::from_str(&file).unwrap();
-
-        // ID of Controller
-        out.push_str(
-            &path.as_path().file_name().unwrap().to_str().unwrap()[..16],
-        );
-
-        // Name of Controller.
-        out.push_str(&file.name);
-        out.push('\t');
-
-        // Type of controller
-        let ctlr_type = match file.r#type.as_str() {
-            "xbox" => 'x',
-            "playstation" => 'p',
-            "nintendo" => 'n',
-            "gamepad" => 'g',
-            "flight" => 'f',
-            _type => panic!("Unknown type: {}", _type),
-        };
-        out.push(ctlr_type);
-
-        // Add remappings
-        let mut kv = Vec::new();
-        for (key, value) in file.remap {
-            kv.push((key, value));
-        }
-        kv.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()));
-        for (key, value) in kv {
-            if let Ok(number) = key.parse::<u8>() {
-                write!(&mut out, "{:02X}", number | 0x80).unwrap();
-            } else {
-                out.push_str(name_to_hex(key.as_str()));
-            }
-            match value {
-                toml::value::Value::String(event) => {
-                    out.push_str(name_to_hex(event.as_str()));
-                    out.push(';');
-                }
-                toml::value::Value::Table(table) => {
-                    if let Some(event) = table.get("event") {
-                        out.push_str(name_to_hex(event.as_str().unwrap()));
-                    } else {
-                        out.push_str(name_to_hex("None"));
-                    }
-                    if let Some(max) = table.get("max") {
-                        let max = max.as_integer().unwrap();
-                        out.push('a');
-                        write!(&mut out, "{}", max).unwrap();
-                    }
-                    if let Some(min) = table.get("min") {
-                        let min = min.as_integer().unwrap();
-                        out.push('i');
-                        write!(&mut out, "{}", min).unwrap();
-                    }
-                    if let Some(scale) = table.get("scale") {
-                        let scale = scale.as_float().unwrap();
-                        out.push('s');
-                        write!(&mut out, "{}", scale).unwrap();
-                    }
-                    if let Some(deadzone) = table.get("deadzone") {
-                        let deadzone = deadzone.as_float().unwrap();
-                        out.push('d');
-                        write!(&mut out, "{}", deadzone).unwrap();
-                    }
-                    out.push(';');
-                }
-                _map => panic!("invalid mapping: {:?}", _map),
-            }
-        }
-        out.pop();
+        let id = &path.as_path().file_name().unwrap().to_str().unwrap()[..16];
+        out.push_str(&mapping::to_sdb(id, &file).unwrap());
 
         // Newline to separate controllers.
         out.push('\n');
@@ -220,22 +170,12 @@ pub(super) fn main() {
             continue;
         }
 
-        // ID of Controller
-        let guid = line.get(0..32).unwrap();
-        // Skip over emulated joysticks.
-        if guid.get(2..8) != Some("000000")
-            || guid.get(12..16) != Some("0000")
-            || guid.get(20..24) != Some("0000")
-            || guid.get(28..32) != Some("0000")
-            || !line.contains("platform:Linux")
-        {
-            continue;
-        }
-
-        out.push_str(&guid.get(0..4).unwrap().to_uppercase());
-        out.push_str(&guid.get(8..12).unwrap().to_uppercase());
-        out.push_str(&guid.get(16..24).unwrap().to_uppercase());
-        out.push_str(&guid.get(24..28).unwrap().to_uppercase());
+        // ID of Controller (skipping over emulated joysticks).
+        let id = match sdl_id(line) {
+            Some(id) if line.contains("platform:Linux") => id,
+            _ => continue,
+        };
+        out.push_str(&id);
 
         // Name of Controller.
         let mut iter = line[33..].split(',');
@@ -247,120 +187,24 @@ pub(super) fn main() {
         out.push('w');
 
         // Add remappings
+        let mut maps = Vec::new();
         for mapping in iter {
             if mapping.is_empty() {
                 continue;
             }
 
-            let mut mapping = mapping.split(':');
-            let js_out = mapping.next().unwrap();
-            let js_in = mapping.next().unwrap();
-
-            let js_in = match js_in {
-                "b0" => name_to_hex("Trigger"),
-                "b1" => name_to_hex("ActionM"),
-                "b2" => name_to_hex("Bumper"),
-                "b3" => name_to_hex("ActionR"),
-                "b4" => name_to_hex("ActionL"),
-                "b5" => name_to_hex("Pinky"),
-                "b6" => "80",
-                "b7" => "81",
-                "b8" => "82",
-                "b9" => "83",
-                "b10" => "84",
-                "b11" => "85",
-                "b12" => "86",
-                "b13" => "87",
-                "b14" => "88",
-                "b15" => "89",
-                "b16" => name_to_hex("ActionA"),
-                "b17" => name_to_hex("ActionB"),
-                "b18" => name_to_hex("ActionC"),
-                "b19" => name_to_hex("ActionV"),
-                "b20" => name_to_hex("ActionH"),
-                "b21" => name_to_hex("ActionD"),
-                "b22" => name_to_hex("BumperL"),
-                "b23" => continue, // FIXME
-                "b24" => continue, // FIXME
-                "b25" => continue, // FIXME
-                "b26" => continue, // FIXME
-                "b30" => continue, // Not a gamepad?
-                "b32" => continue, // Not a gamepad?
-                "b33" => continue, // Not a gamepad?
-                "b36" => continue, // Not a gamepad?
-                "b52" => continue, // Not a gamepad?
-                "b53" => continue, // Not a gamepad?
-                "h0.1" => name_to_hex("PovUp"),
-                "h0.2" => name_to_hex("PovRight"),
-                "h0.4" => name_to_hex("PovDown"),
-                "h0.8" => name_to_hex("PovLeft"),
-                "a0" | "a0~" => name_to_hex("JoyX"),
-                "a1" | "a1~" => name_to_hex("JoyY"),
-                "a2" | "a2~" => name_to_hex("JoyZ"),
-                "a3" | "a3~" => name_to_hex("CamX"),
-                "a4" | "a4~" => name_to_hex("CamY"),
-                "a5" | "a5~" => name_to_hex("CamZ"),
-                "a6" | "a6~" => name_to_hex("Throttle"),
-                "a7" | "a7~" => name_to_hex("Rudder"),
-                "a8" | "a8~" => name_to_hex("Wheel"),
-                "a9" | "a9~" => name_to_hex("Gas"),
-                "a10" | "a10~" => name_to_hex("Brake"),
-                "a11" | "a11~" => name_to_hex("Slew"),
-                "a12" => name_to_hex("ThrottleL"),
-                "a13" => name_to_hex("ThrottleR"),
-                "a14" => name_to_hex("ScrollX"),
-                "+a0" | "+a1" | "+a2" | "+a3" | "+a4" | "+a5" | "-a0"
-                | "-a1" | "-a2" | "-a3" | "-a4" | "-a5" => continue,
-                "Linux" => continue,
-                // ?
-                "b122" => name_to_hex("Down"),
-                "b119" => name_to_hex("Left"),
-                "b120" => name_to_hex("Right"),
-                "b117" => name_to_hex("Up"),
-                "b161" => "8B",
-                "b136" => continue,
-                _in => panic!("Unknown input {}", _in),
+            let (js_out, js_in) = match mapping.split_once(':') {
+                Some(("platform" | "crc" | "hint", _)) | None => continue,
+                Some(pair) => pair,
             };
-
-            let js_out = match js_out {
-                "a" => "02",
-                "b" => "03",
-                "x" => "05",
-                "y" => "06",
-                "back" => "08",
-                "start" => "09",
-                "guide" => "01",
-                "leftshoulder" => "0C",
-                "lefttrigger" => "0E",
-                "leftx" => "20",
-                "lefty" => "21",
-                "rightx" => "23",
-                "righty" => "24",
-                "rightshoulder" => "0D",
-                "righttrigger" => "0F",
-                "leftstick" => "0A",
-                "rightstick" => "0B",
-                "dpleft" => "12",
-                "dpright" => "13",
-                "dpup" => "10",
-                "dpdown" => "11",
-                "misc1" => "81",
-                "+leftx" | "-leftx" | "+lefty" | "-lefty" => continue,
-                "+rightx" | "-rightx" | "+righty" | "-righty" => continue,
-                "touchpad" => continue,
-                "paddle1" => "51",
-                "paddle2" => "52",
-                "paddle3" => "53",
-                "paddle4" => "54",
-                _out => panic!("Unknown output {}", _out),
-            };
-
-            out.push_str(js_in);
-            out.push_str(js_out);
-            // FIXME: Tweaks
-            out.push(';');
+            match sdl_mapping(js_out, js_in) {
+                Ok(mapping) => maps.push(mapping),
+                Err(error) => {
+                    eprintln!("Skipping {} for {}: {}", js_out, name, error);
+                }
+            }
         }
-        out.pop();
+        out.push_str(&maps.join(";"));
         out.push('\n');
     }
     out.pop();